@@ -1,75 +1,262 @@
-use beciarz_core::{greek_to_official, official_to_greek};
-use clap::{Parser, ValueEnum};
-use std::io::{self, Read};
+use beciarz_core::custom::CustomScript;
+use beciarz_core::greek::{
+    GreekScript, GreekVariantScript, Normalization, Scheme, Variant, normalize,
+};
+use beciarz_core::{Registry, Script, UnknownPolicy};
+use clap::builder::{PossibleValue, PossibleValuesParser};
+use clap::{CommandFactory, FromArgMatches, Parser};
+use encoding_rs::Encoding;
+use std::io::{self, Read, Write};
+use std::path::PathBuf;
 
 #[derive(Parser)]
 #[command(name = "beciarz", about = "tłumaczy alfabety")]
 struct Cli {
-    /// Input format (g/grecki or o/oficjalny)
-    #[arg(
-        short = 'i',
-        long = "input-format",
-        value_enum,
-        default_value = "oficjalny"
-    )]
-    input_fmt: Format,
-
-    /// Output format (g/grecki or o/oficjalny)
-    #[arg(
-        short = 'o',
-        long = "output-format",
-        value_enum,
-        default_value = "grecki"
-    )]
-    output_fmt: Format,
+    /// Input format
+    #[arg(short = 'i', long = "input-format", default_value = "oficjalny")]
+    input_fmt: String,
+
+    /// Output format
+    #[arg(short = 'o', long = "output-format", default_value = "grecki")]
+    output_fmt: String,
+
+    /// Load a script from a TOML or JSON file, to be used by its name as either format
+    #[arg(long = "script-file", value_name = "FILE")]
+    script_files: Vec<PathBuf>,
+
+    /// Write and read the Greek format in a variant: hacek, beta, diaeresis (not with beta),
+    /// zwnj and soft-labials, joined with '+'
+    #[arg(long = "greek-variant", value_name = "VARIANT", value_parser = clap::value_parser!(Variant))]
+    greek_variant: Option<Variant>,
+
+    /// Write and read the Greek format with the tables of a scheme version, so that the text
+    /// reads the same with later releases: v1
+    #[arg(long = "scheme", value_parser = clap::value_parser!(Scheme))]
+    scheme: Option<Scheme>,
 
     /// Do not print the trailing newline (like echo -n)
     #[arg(short = 'n')]
     no_newline: bool,
 
+    /// Fail on letters that cannot be translated instead of keeping them as they are
+    #[arg(short = 's', long = "strict")]
+    strict: bool,
+
+    /// Write the text the way it is pronounced: "chleb" becomes "chlep"
+    #[arg(short = 'p', long = "pronounced")]
+    pronounced: bool,
+
+    /// Make sure the output converts back to exactly the input, marking words where needed.
+    /// Not with --pronounced, which changes the sounds on purpose and so cannot convert back;
+    /// no effect for ipa and x-sampa, which have no mark to split words with
+    #[arg(short = 'l', long = "lossless", conflicts_with = "pronounced")]
+    lossless: bool,
+
+    /// Write Greek accents as one code point (nfc) or as a letter and a combining acute (nfd)
+    #[arg(long = "normalization", value_parser = ["nfc", "nfd"])]
+    normalization: Option<String>,
+
+    /// Convert stdin line by line as it comes, e.g. from `tail -f`
+    #[arg(long = "stream", conflicts_with_all = ["input", "input_encoding", "output_encoding", "normalization"])]
+    stream: bool,
+
+    /// Encoding of the text read from stdin
+    #[arg(long = "input-encoding", value_parser = ENCODINGS, default_value = "utf-8")]
+    input_encoding: String,
+
+    /// Encoding of the output; characters it cannot hold are written as '?'
+    /// (or fail with --strict)
+    #[arg(long = "output-encoding", value_parser = ENCODINGS, default_value = "utf-8")]
+    output_encoding: String,
+
     /// The text to transform. If omitted, reads from stdin.
     input: Option<String>,
 }
-#[derive(Copy, Clone, PartialEq, Eq, ValueEnum)]
-enum Format {
-    /// Official / Oficjalny
-    #[clap(name = "oficjalny", alias = "o")]
-    Official,
-    /// Greek / Grecki
-    #[clap(name = "grecki", alias = "g")]
-    Greek,
+
+const ENCODINGS: [&str; 4] = ["utf-8", "iso-8859-2", "windows-1250", "iso-8859-7"];
+
+const BOM: char = '\u{feff}';
+
+/// Every registered script is a valid format.
+fn script_names(registry: &Registry) -> PossibleValuesParser {
+    PossibleValuesParser::new(registry.scripts().map(|script| {
+        PossibleValue::new(script.name().to_string())
+            .aliases(script.aliases().iter().map(|alias| alias.to_string()))
+            .help(script.description().to_string())
+    }))
 }
 
 fn main() -> io::Result<()> {
-    let cli = Cli::parse();
+    let registry = registry();
+    let matches = Cli::command()
+        .mut_arg("input_fmt", |arg| arg.value_parser(script_names(&registry)))
+        .mut_arg("output_fmt", |arg| {
+            arg.value_parser(script_names(&registry))
+        })
+        .get_matches();
+    let mut cli = Cli::from_arg_matches(&matches).unwrap_or_else(|e| e.exit());
+    if let Some(greek) = greek_script(cli.greek_variant, cli.scheme) {
+        for format in [&mut cli.input_fmt, &mut cli.output_fmt] {
+            if script(&registry, format).name() == GreekScript.name() {
+                *format = greek.name().to_string();
+            }
+        }
+    }
+
+    // both ways of reading the input convert it with the same options
+    let converter = registry
+        .converter(&cli.input_fmt, &cli.output_fmt)
+        .expect("clap only accepts registered script names")
+        .unknown(if cli.strict {
+            UnknownPolicy::Error
+        } else {
+            UnknownPolicy::Keep
+        })
+        .lossless(cli.lossless)
+        .pronounced(cli.pronounced)
+        .build();
+    // If input and output are the same, the text is given back as it is
+    let same = converter.from().name() == converter.to().name() && !cli.pronounced;
+
+    if cli.stream {
+        let stdout = io::stdout();
+        let mut output = io::LineWriter::new(stdout.lock());
+        let streamed = match same {
+            true => io::copy(&mut io::stdin().lock(), &mut output).map(drop),
+            false => converter.convert_stream(io::stdin().lock(), output),
+        };
+        if let Err(e) = streamed {
+            eprintln!("beciarz: {e}");
+            std::process::exit(1)
+        }
+        return Ok(());
+    }
 
     // 1. Resolve the text
     let text = match cli.input {
         Some(t) => t,
         None => {
-            let mut buffer = String::new();
-            io::stdin().read_to_string(&mut buffer)?;
-            buffer
+            let mut buffer = vec![];
+            io::stdin().read_to_end(&mut buffer)?;
+            decode(&buffer, encoding(&cli.input_encoding))?
         }
     };
+    // a byte order mark and Windows line endings are given back as they came
+    let (bom, text) = match text.strip_prefix(BOM) {
+        Some(rest) => (true, rest.to_string()),
+        None => (false, text),
+    };
+    let newline = if text.contains("\r\n") { "\r\n" } else { "\n" };
 
-    // 2. Logic Matrix
-    // We only have two functions, so we handle the 4 possible combinations:
-    let result = match (cli.input_fmt, cli.output_fmt) {
-        (Format::Official, Format::Greek) => official_to_greek(&text),
-        (Format::Greek, Format::Official) => greek_to_official(&text),
-        _ => {
-            // If input and output are the same, just return the text as is
-            text
-        }
+    // 2. Convert through the sound layer
+    let result = if same {
+        text
+    } else {
+        converter.convert(&text).unwrap_or_else(|e| {
+            eprintln!("beciarz: {e}");
+            std::process::exit(1)
+        })
+    };
+
+    let result = match cli.normalization.as_deref() {
+        Some("nfd") => normalize(&result, Normalization::Nfd),
+        Some(_) => normalize(&result, Normalization::Nfc),
+        None => result,
     };
 
     // 3. Output
-    if cli.no_newline {
-        print!("{result}");
-    } else {
-        println!("{result}");
+    let output_encoding = encoding(&cli.output_encoding);
+    let mut output = String::new();
+    if bom && output_encoding == encoding_rs::UTF_8 {
+        output.push(BOM);
+    }
+    output.push_str(&result);
+    if !cli.no_newline {
+        output.push_str(newline);
     }
+    let bytes = encode(&output, output_encoding, cli.strict).unwrap_or_else(|c| {
+        eprintln!("beciarz: cannot write {c:?} in {}", output_encoding.name());
+        std::process::exit(1)
+    });
+    io::stdout().write_all(&bytes)?;
 
     Ok(())
 }
+
+/// The built-in scripts, the Greek script given with --greek-variant and --scheme and the
+/// scripts given with --script-file, which must be loaded before the formats can be checked.
+fn registry() -> Registry {
+    let mut registry = Registry::default();
+    let Ok(matches) = Cli::command().ignore_errors(true).try_get_matches() else {
+        return registry;
+    };
+    let variant = matches.get_one::<Variant>("greek_variant").copied();
+    let scheme = matches.get_one::<Scheme>("scheme").copied();
+    if let Some(greek) = greek_script(variant, scheme) {
+        registry.register(greek);
+    }
+    for path in matches
+        .get_many::<PathBuf>("script_files")
+        .into_iter()
+        .flatten()
+    {
+        match CustomScript::load(path) {
+            Ok(script) => registry.register(script),
+            Err(e) => {
+                eprintln!("beciarz: {e}");
+                std::process::exit(1)
+            }
+        }
+    }
+    registry
+}
+
+/// The script the Greek format stands for, unless it is the built-in one.
+fn greek_script(variant: Option<Variant>, scheme: Option<Scheme>) -> Option<GreekVariantScript> {
+    if variant.is_none() && scheme.is_none() {
+        return None;
+    }
+    Some(GreekVariantScript::with_scheme(
+        variant.unwrap_or_default(),
+        scheme.unwrap_or_default(),
+    ))
+}
+
+fn encoding(label: &str) -> &'static Encoding {
+    Encoding::for_label(label.as_bytes()).expect("clap only accepts known encodings")
+}
+
+fn decode(bytes: &[u8], encoding: &'static Encoding) -> io::Result<String> {
+    let (text, had_errors) = encoding.decode_without_bom_handling(bytes);
+    if had_errors {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("input is not valid {}", encoding.name()),
+        ));
+    }
+    Ok(text.into_owned())
+}
+
+/// The text in the given encoding. Characters the encoding cannot hold become `?`,
+/// or the first of them is returned as the error if `strict`.
+fn encode(text: &str, encoding: &'static Encoding, strict: bool) -> Result<Vec<u8>, char> {
+    if encoding == encoding_rs::UTF_8 {
+        return Ok(text.as_bytes().to_vec());
+    }
+    let mut bytes = Vec::with_capacity(text.len());
+    let mut buffer = [0; 4];
+    for c in text.chars() {
+        match encoding.encode(c.encode_utf8(&mut buffer)) {
+            (encoded, _, false) => bytes.extend_from_slice(&encoded),
+            _ if strict => return Err(c),
+            _ => bytes.push(b'?'),
+        }
+    }
+    Ok(bytes)
+}
+
+fn script<'a>(registry: &'a Registry, name: &str) -> &'a dyn Script {
+    registry
+        .get(name)
+        .expect("clap only accepts registered script names")
+}
@@ -0,0 +1,89 @@
+//! Conversion speed on a larger text, without any benchmarking framework:
+//! `cargo bench -p beciarz-core`.
+
+use std::hint::black_box;
+use std::time::{Duration, Instant};
+
+use beciarz_core::cyrillic::CyrillicScript;
+use beciarz_core::greek::GreekScript;
+use beciarz_core::official::{OfficialScript, Text, TextRepr};
+use beciarz_core::{Converter, Script, convert, greek_to_official, official_to_greek};
+
+const SAMPLE: &str = "Litwo! Ojczyzno moja! ty jesteś jak zdrowie. \
+Ile cię trzeba cenić, ten tylko się dowie, kto cię stracił. \
+Dziś piękność twą w całej ozdobie widzę i opisuję, bo tęsknię po tobie.\n";
+
+/// Runs `f` repeatedly for about a second, prints the throughput and returns the time per run.
+fn bench(name: &str, bytes: usize, mut f: impl FnMut()) -> Duration {
+    f();
+    let start = Instant::now();
+    let mut runs = 0;
+    while start.elapsed() < Duration::from_secs(1) {
+        f();
+        runs += 1;
+    }
+    let per_run = start.elapsed() / runs;
+    let throughput = bytes as f64 / per_run.as_secs_f64() / 1e6;
+    println!("{name:<32} {per_run:>12.2?}/run {throughput:>8.2} MB/s");
+    per_run
+}
+
+/// Prints how many times as fast writing into one buffer is as writing word by word.
+fn faster(name: &str, one_buffer: Duration, word_by_word: Duration) {
+    let times = word_by_word.as_secs_f64() / one_buffer.as_secs_f64();
+    println!("{name}: {times:.2}x as fast as word by word");
+}
+
+/// Encodes the way the pipeline did before every word was written into one buffer:
+/// each word into a `String` of its own, capitalised into another, then all joined.
+fn encode_word_by_word(script: &dyn Script, text: &Text) -> String {
+    text.parts
+        .iter()
+        .map(|part| match part {
+            TextRepr::Arbitrary(arbitrary) => arbitrary.clone(),
+            TextRepr::Word(sounds, capitalisation) => {
+                capitalisation.apply(&script.encode_word(sounds))
+            }
+        })
+        .collect::<Vec<String>>()
+        .concat()
+}
+
+fn main() {
+    let official = SAMPLE.repeat(200);
+    let greek = official_to_greek(&official);
+    let converter = Converter::builder(OfficialScript, GreekScript).build();
+
+    let helper = bench("official_to_greek", official.len(), || {
+        black_box(official_to_greek(black_box(&official)));
+    });
+    let word_by_word = bench("official_to_greek, word by word", official.len(), || {
+        let text = OfficialScript.decode(black_box(&official));
+        black_box(encode_word_by_word(&GreekScript, &text));
+    });
+    faster("official_to_greek", helper, word_by_word);
+    bench("greek_to_official", greek.len(), || {
+        black_box(greek_to_official(black_box(&greek)));
+    });
+    bench("Converter::convert", official.len(), || {
+        black_box(converter.convert(black_box(&official)).unwrap());
+    });
+    bench("official -> cyrillic", official.len(), || {
+        black_box(convert(
+            black_box(&official),
+            &OfficialScript,
+            &CyrillicScript,
+        ));
+    });
+    bench("decode official", official.len(), || {
+        black_box(OfficialScript.decode(black_box(&official)));
+    });
+    let text = OfficialScript.decode(&official);
+    let encode = bench("encode greek", official.len(), || {
+        black_box(GreekScript.encode(black_box(&text)));
+    });
+    let word_by_word = bench("encode greek, word by word", official.len(), || {
+        black_box(encode_word_by_word(&GreekScript, black_box(&text)));
+    });
+    faster("encode greek", encode, word_by_word);
+}
@@ -0,0 +1,234 @@
+use std::ops::Range;
+
+use crate::official::{Text, TextRepr};
+use crate::script::{encode_with, sound_starts};
+use crate::{Script, Sound, fold_case};
+
+/// A piece of the input and the piece of the output it was converted to, as byte ranges.
+#[derive(PartialEq, Eq, Debug, Clone)]
+pub struct Span {
+    pub source: Range<usize>,
+    pub output: Range<usize>,
+}
+
+/// How the output of a conversion lines up with its input, see [`convert_aligned`].
+#[derive(PartialEq, Eq, Debug, Clone, Default)]
+pub struct Alignment {
+    /// A span for every word, in order.
+    pub words: Vec<Span>,
+    /// A span for every [`Sound`] of every word, in order. Sounds read from or written with
+    /// the same letters (`x` for `[K, S]`) share their ranges.
+    pub sounds: Vec<Span>,
+}
+
+/// Like [`crate::convert`], but also tells which part of the input every word
+/// and every sound of the output comes from.
+///
+/// ```
+/// use beciarz_core::{convert_aligned, greek::GreekScript, official::OfficialScript};
+///
+/// let (output, alignment) = convert_aligned("ala ma kota", &OfficialScript, &GreekScript);
+/// let kota = &alignment.words[2];
+/// assert_eq!(&output[kota.output.clone()], "κοτα");
+/// assert_eq!(kota.source, 7..11);
+/// ```
+pub fn convert_aligned(input: &str, from: &dyn Script, to: &dyn Script) -> (String, Alignment) {
+    let text = from.decode(input);
+    let source = read_spans(input, &text, from);
+    let mut ranges = vec![];
+    let output = encode_with(to, &text, true, Some(&mut ranges), |sounds, output| {
+        to.encode_word_into(sounds, output);
+        Ok(())
+    })
+    .expect("encode_word never fails");
+    let alignment = align(&text, &source, &output, &ranges, to);
+    (output, alignment)
+}
+
+/// Where a part of a decoded text was read from: its byte range in the input and,
+/// for a word, the byte range of every sound.
+pub(crate) struct PartSpan {
+    pub range: Range<usize>,
+    pub sounds: Vec<Range<usize>>,
+}
+
+/// Finds every part of a decoded text in the input it was decoded from.
+///
+/// Text between words is taken to be read char by char, even where a script changes it
+/// (`;` for `?` in Greek). A word ends where the text after it starts, or where writing it
+/// back stops agreeing with the input if another word follows directly.
+pub(crate) fn read_spans(input: &str, text: &Text, from: &dyn Script) -> Vec<PartSpan> {
+    let mut spans = Vec::with_capacity(text.parts.len());
+    let mut at = 0;
+    for (i, part) in text.parts.iter().enumerate() {
+        let rest = &input[at..];
+        let (skipped, len) = match part {
+            TextRepr::Arbitrary(arbitrary) => {
+                let len = rest
+                    .char_indices()
+                    .nth(arbitrary.chars().count())
+                    .map_or(rest.len(), |(len, _)| len);
+                (0, len)
+            }
+            TextRepr::Word(sounds, _) => {
+                let after_word = i > 0 && matches!(text.parts[i - 1], TextRepr::Word(..));
+                let marker = from.break_marker();
+                // the marker between two words is not part of either
+                let skipped = if after_word && !marker.is_empty() && rest.starts_with(marker) {
+                    marker.len()
+                } else {
+                    0
+                };
+                let rest = &rest[skipped..];
+                let len = match text.parts.get(i + 1) {
+                    Some(TextRepr::Arbitrary(arbitrary)) => rest
+                        .find(arbitrary.as_str())
+                        .or_else(|| rest.find(&*from.localize_punctuation(arbitrary))),
+                    Some(TextRepr::Word(..)) => Some(agreeing(&from.encode_word(sounds), rest)),
+                    None => None,
+                };
+                (skipped, len.unwrap_or(rest.len()))
+            }
+        };
+        let range = at + skipped..at + skipped + len;
+        let sounds = match part {
+            TextRepr::Word(sounds, _) => {
+                sound_ranges(from, sounds, &input[range.clone()], range.start)
+            }
+            TextRepr::Arbitrary(_) => vec![],
+        };
+        at = range.end;
+        spans.push(PartSpan { range, sounds });
+    }
+    spans
+}
+
+/// Joins the spans of words that follow each other directly, the way
+/// [`crate::phonetics::pronounce`] joins the words.
+pub(crate) fn merge_adjacent(spans: Vec<PartSpan>, text: &Text) -> Vec<PartSpan> {
+    let mut merged: Vec<PartSpan> = Vec::with_capacity(spans.len());
+    let mut after_word = false;
+    for (part, span) in text.parts.iter().zip(spans) {
+        let word = matches!(part, TextRepr::Word(..));
+        match merged.last_mut() {
+            Some(last) if word && after_word => {
+                last.range.end = span.range.end;
+                last.sounds.extend(span.sounds);
+            }
+            _ => merged.push(span),
+        }
+        after_word = word;
+    }
+    merged
+}
+
+/// Pairs the parts of a text as read with the same parts as written in `output`.
+///
+/// Sounds are paired only in words that have as many sounds as they were read with.
+pub(crate) fn align(
+    text: &Text,
+    source: &[PartSpan],
+    output: &str,
+    ranges: &[Range<usize>],
+    to: &dyn Script,
+) -> Alignment {
+    let mut alignment = Alignment::default();
+    for ((part, source), range) in text.parts.iter().zip(source).zip(ranges) {
+        let TextRepr::Word(sounds, _) = part else {
+            continue;
+        };
+        alignment.words.push(Span {
+            source: source.range.clone(),
+            output: range.clone(),
+        });
+        let written = sound_ranges(to, sounds, &output[range.clone()], range.start);
+        if written.len() == source.sounds.len() {
+            alignment
+                .sounds
+                .extend(
+                    source
+                        .sounds
+                        .iter()
+                        .zip(written)
+                        .map(|(source, output)| Span {
+                            source: source.clone(),
+                            output,
+                        }),
+                );
+        }
+    }
+    alignment
+}
+
+/// The byte range of every sound of a word written as `word`, which starts at `offset`.
+fn sound_ranges(
+    script: &dyn Script,
+    sounds: &[Sound],
+    word: &str,
+    offset: usize,
+) -> Vec<Range<usize>> {
+    let letters = word.chars().collect::<Vec<char>>();
+    let starts = sound_starts(script, sounds, &letters);
+    let byte = |k: usize| offset + word.char_indices().nth(k).map_or(word.len(), |(b, _)| b);
+    starts
+        .iter()
+        .map(|&start| {
+            let end = starts
+                .iter()
+                .copied()
+                .find(|&s| s > start)
+                .unwrap_or(letters.len());
+            byte(start)..byte(end)
+        })
+        .collect()
+}
+
+/// How many bytes at the start of `input` agree with the written word, whatever their case.
+fn agreeing(written: &str, input: &str) -> usize {
+    written
+        .chars()
+        .zip(input.char_indices())
+        .take_while(|&(a, (_, b))| a == fold_case(b))
+        .last()
+        .map_or(0, |(_, (at, b))| at + b.len_utf8())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cyrillic::CyrillicScript;
+    use crate::greek::GreekScript;
+    use crate::official::OfficialScript;
+
+    fn pieces<'a>(input: &'a str, output: &'a str, spans: &[Span]) -> Vec<(&'a str, &'a str)> {
+        spans
+            .iter()
+            .map(|span| (&input[span.source.clone()], &output[span.output.clone()]))
+            .collect()
+    }
+
+    #[test]
+    fn words_and_sounds() {
+        let input = "Cicho, chrząszcz!";
+        let (output, alignment) = convert_aligned(input, &OfficialScript, &GreekScript);
+        assert_eq!(
+            pieces(input, &output, &alignment.words),
+            [("Cicho", "Τίχο"), ("chrząszcz", "χρώψθ")]
+        );
+        assert_eq!(
+            pieces(input, &output, &alignment.sounds[..4]),
+            [("Ci", "Τ"), ("Ci", "ί"), ("ch", "χ"), ("o", "ο")]
+        );
+        assert_eq!(alignment.sounds.len(), 4 + 5);
+    }
+
+    #[test]
+    fn adjacent_words() {
+        let input = "ποδ\\ζέλίτ' τω";
+        let (output, alignment) = convert_aligned(input, &GreekScript, &CyrillicScript);
+        assert_eq!(
+            pieces(input, &output, &alignment.words),
+            [("ποδ", "под"), ("ζέλίτ'", "зелить"), ("τω", "тѫ")]
+        );
+    }
+}
@@ -0,0 +1,487 @@
+use std::collections::HashMap;
+use std::io::{self, BufRead};
+use std::ops::Range;
+use std::sync::Arc;
+use std::{fmt, str};
+
+use crate::align::{Alignment, align, merge_adjacent, read_spans};
+use crate::official::{Text, TextRepr};
+use crate::phonetics::pronounce;
+use crate::script::{encode_with, split_unreadable};
+use crate::{CapitalisationMode, Error, Script, Sound};
+
+/// How long a line [`Converter::convert_stream`] waits for the end of, in bytes,
+/// before converting what it has up to the last space.
+const MAX_LINE: usize = 64 * 1024;
+
+/// What to do with letters the source script cannot read.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum UnknownPolicy {
+    /// Copy them to the output as they are.
+    #[default]
+    Keep,
+    /// Leave them out.
+    Drop,
+    /// Write the given character in their place.
+    Replace(char),
+    /// Fail on the first letter or sound that cannot be converted.
+    Error,
+}
+
+/// How the words of the output are capitalised.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum CasePolicy {
+    /// The way they were in the source.
+    #[default]
+    Preserve,
+    /// All in lowercase.
+    Lowercase,
+    /// All in uppercase.
+    Uppercase,
+}
+
+/// A conversion between two scripts with its options, built once and used for any number of texts.
+///
+/// Cheap to clone and can be shared between threads.
+///
+/// ```
+/// use beciarz_core::{Converter, UnknownPolicy, greek::GreekScript, official::OfficialScript};
+///
+/// let converter = Converter::builder(OfficialScript, GreekScript)
+///     .unknown(UnknownPolicy::Replace('?'))
+///     .dictionary([("Kraków", "Κρακόβ")])
+///     .build();
+/// assert_eq!(converter.convert("Kraków, Straße").unwrap(), "Κρακόβ, Στρα?ε");
+/// ```
+#[derive(Clone)]
+pub struct Converter {
+    from: Arc<dyn Script>,
+    to: Arc<dyn Script>,
+    unknown: UnknownPolicy,
+    case: CasePolicy,
+    localize_punctuation: bool,
+    lossless: bool,
+    pronounced: bool,
+    dictionary: HashMap<Vec<Sound>, String>,
+}
+
+/// Sets the options of a [`Converter`], see [`Converter::builder`].
+pub struct ConverterBuilder {
+    converter: Converter,
+    dictionary: Vec<(String, String)>,
+}
+
+impl Converter {
+    /// Starts building a conversion from one script to another. Every option starts at its default:
+    /// unknown letters are kept, the case is preserved and punctuation is localized.
+    pub fn builder(from: impl Script + 'static, to: impl Script + 'static) -> ConverterBuilder {
+        Self::builder_shared(Arc::new(from), Arc::new(to))
+    }
+
+    pub(crate) fn builder_shared(from: Arc<dyn Script>, to: Arc<dyn Script>) -> ConverterBuilder {
+        ConverterBuilder {
+            converter: Converter {
+                from,
+                to,
+                unknown: UnknownPolicy::default(),
+                case: CasePolicy::default(),
+                localize_punctuation: true,
+                lossless: false,
+                pronounced: false,
+                dictionary: HashMap::new(),
+            },
+            dictionary: vec![],
+        }
+    }
+
+    /// Converts a text.
+    ///
+    /// Fails only with [`UnknownPolicy::Error`].
+    pub fn convert(&self, input: &str) -> Result<String, Error> {
+        let mut text = self.decode(input)?;
+        self.apply_policies(&mut text);
+        if self.pronounced {
+            text = pronounce(&text);
+        }
+        self.encode(&text, None)
+    }
+
+    /// Like [`Converter::convert`], but also tells which part of the input every word
+    /// and every sound of the output comes from, see [`crate::convert_aligned`].
+    pub fn convert_aligned(&self, input: &str) -> Result<(String, Alignment), Error> {
+        let mut text = self.decode(input)?;
+        let mut source = read_spans(input, &text, self.from.as_ref());
+        self.apply_policies(&mut text);
+        if self.pronounced {
+            source = merge_adjacent(source, &text);
+            text = pronounce(&text);
+        }
+        let mut ranges = vec![];
+        let output = self.encode(&text, Some(&mut ranges))?;
+        let alignment = align(&text, &source, &output, &ranges, self.to.as_ref());
+        Ok((output, alignment))
+    }
+
+    fn decode(&self, input: &str) -> Result<Text, Error> {
+        let strict = self.unknown == UnknownPolicy::Error;
+        let lossless = || {
+            let text = self.from.decode_lossless(input);
+            split_unreadable(text, self.from.as_ref(), self.to.as_ref())
+        };
+        Ok(match (strict, self.lossless) {
+            (true, false) => self.from.try_decode(input)?,
+            (true, true) => {
+                self.from.try_decode(input)?;
+                lossless()
+            }
+            (false, false) => self.from.decode(input),
+            (false, true) => lossless(),
+        })
+    }
+
+    fn encode(&self, text: &Text, ranges: Option<&mut Vec<Range<usize>>>) -> Result<String, Error> {
+        let strict = self.unknown == UnknownPolicy::Error;
+        encode_with(
+            self.to.as_ref(),
+            text,
+            self.localize_punctuation,
+            ranges,
+            |sounds, output| {
+                match self.dictionary.get(sounds) {
+                    Some(word) => output.push_str(word),
+                    None if strict => output.push_str(&self.to.try_encode_word(sounds)?),
+                    None => self.to.encode_word_into(sounds, output),
+                }
+                Ok(())
+            },
+        )
+    }
+
+    /// Converts text read from `input` piece by piece and writes each piece to `output`
+    /// as soon as it is converted, so the whole text never has to be in memory.
+    ///
+    /// The input is cut only at line ends, and nothing a script reads (words, quotes around
+    /// a phrase) goes on past the end of a line, so every line converts the same as it would
+    /// within the whole text. Only a line longer than 64 KiB is cut between words.
+    /// With [`UnknownPolicy::Error`] the offset of the error counts from the start
+    /// of the whole input.
+    pub fn convert_stream(
+        &self,
+        input: impl BufRead,
+        mut output: impl io::Write,
+    ) -> io::Result<()> {
+        self.stream(input, |converted| {
+            output.write_all(converted.as_bytes())?;
+            output.flush()
+        })
+    }
+
+    /// Like [`Converter::convert_stream`], but writes to a [`fmt::Write`].
+    pub fn convert_stream_fmt(
+        &self,
+        input: impl BufRead,
+        output: &mut impl fmt::Write,
+    ) -> io::Result<()> {
+        self.stream(input, |converted| {
+            output.write_str(converted).map_err(io::Error::other)
+        })
+    }
+
+    fn stream(
+        &self,
+        mut input: impl BufRead,
+        mut write: impl FnMut(&str) -> io::Result<()>,
+    ) -> io::Result<()> {
+        let mut pending = vec![];
+        let mut offset = 0;
+        loop {
+            let available = input.fill_buf()?;
+            let end = available.is_empty();
+            pending.extend_from_slice(available);
+            let consumed = available.len();
+            input.consume(consumed);
+
+            let valid = match str::from_utf8(&pending) {
+                Ok(text) => text,
+                // a character cut in two by the end of the chunk, the rest comes with the next one
+                Err(e) if e.error_len().is_none() && !end => {
+                    str::from_utf8(&pending[..e.valid_up_to()]).expect("checked to be valid")
+                }
+                Err(e) => return Err(io::Error::new(io::ErrorKind::InvalidData, e)),
+            };
+            // a line may go on in the next chunk, so only text up to the last line end is complete
+            let complete = match valid.rfind('\n') {
+                _ if end => valid.len(),
+                Some(newline) => newline + 1,
+                None if valid.len() > MAX_LINE => match valid.rfind(char::is_whitespace) {
+                    Some(space) => space + valid[space..].chars().next().map_or(0, char::len_utf8),
+                    None => valid.len(),
+                },
+                None => 0,
+            };
+
+            if complete > 0 {
+                let piece = &valid[..complete];
+                let converted = self.convert(piece).map_err(|e| {
+                    let e = match e {
+                        Error::UnknownLetter { offset: at, ch } => Error::UnknownLetter {
+                            offset: offset + at,
+                            ch,
+                        },
+                        e => e,
+                    };
+                    io::Error::new(io::ErrorKind::InvalidData, e)
+                })?;
+                offset += piece.chars().count();
+                write(&converted)?;
+                pending.drain(..complete);
+            }
+            if end {
+                return Ok(());
+            }
+        }
+    }
+
+    /// The script the text is converted from.
+    pub fn from(&self) -> &dyn Script {
+        self.from.as_ref()
+    }
+
+    /// The script the text is converted to.
+    pub fn to(&self) -> &dyn Script {
+        self.to.as_ref()
+    }
+
+    fn apply_policies(&self, text: &mut Text) {
+        for part in &mut text.parts {
+            match part {
+                // whatever letters are left between the words could not be read
+                TextRepr::Arbitrary(arbitrary) => {
+                    *arbitrary = match self.unknown {
+                        UnknownPolicy::Keep | UnknownPolicy::Error => continue,
+                        UnknownPolicy::Drop => {
+                            arbitrary.chars().filter(|c| !c.is_alphabetic()).collect()
+                        }
+                        UnknownPolicy::Replace(replacement) => arbitrary
+                            .chars()
+                            .map(|c| if c.is_alphabetic() { replacement } else { c })
+                            .collect(),
+                    }
+                }
+                TextRepr::Word(_, capitalisation_mode) => match self.case {
+                    CasePolicy::Preserve => {}
+                    CasePolicy::Lowercase => *capitalisation_mode = CapitalisationMode::Lowercase,
+                    CasePolicy::Uppercase => *capitalisation_mode = CapitalisationMode::Uppercase,
+                },
+            }
+        }
+    }
+}
+
+impl ConverterBuilder {
+    /// What to do with letters that cannot be converted, [`UnknownPolicy::Keep`] by default.
+    pub fn unknown(mut self, policy: UnknownPolicy) -> Self {
+        self.converter.unknown = policy;
+        self
+    }
+
+    /// How to capitalise the output, [`CasePolicy::Preserve`] by default.
+    pub fn case(mut self, policy: CasePolicy) -> Self {
+        self.converter.case = policy;
+        self
+    }
+
+    /// Whether punctuation is written the way the target script does, see
+    /// [`Script::localize_punctuation`]. On by default.
+    pub fn localize_punctuation(mut self, localize: bool) -> Self {
+        self.converter.localize_punctuation = localize;
+        self
+    }
+
+    /// Whether the output must convert back to exactly the input, see [`crate::convert_lossless`].
+    /// Off by default.
+    pub fn lossless(mut self, lossless: bool) -> Self {
+        self.converter.lossless = lossless;
+        self
+    }
+
+    /// Whether the words are written the way they are pronounced, see [`crate::phonetics`].
+    /// Off by default.
+    pub fn pronounced(mut self, pronounced: bool) -> Self {
+        self.converter.pronounced = pronounced;
+        self
+    }
+
+    /// Words written in a fixed way instead of by the rules: every entry is a word
+    /// in the source script and how to write it (in lowercase) in the target script.
+    ///
+    /// A word matches an entry when it has the same sounds, whatever its case.
+    pub fn dictionary<K: Into<String>, V: Into<String>>(
+        mut self,
+        entries: impl IntoIterator<Item = (K, V)>,
+    ) -> Self {
+        self.dictionary
+            .extend(entries.into_iter().map(|(k, v)| (k.into(), v.into())));
+        self
+    }
+
+    /// The configured [`Converter`].
+    pub fn build(mut self) -> Converter {
+        for (word, written) in self.dictionary {
+            let sounds = self
+                .converter
+                .from
+                .decode(&word)
+                .parts
+                .into_iter()
+                .flat_map(|part| match part {
+                    TextRepr::Word(sounds, _) => sounds,
+                    TextRepr::Arbitrary(_) => vec![],
+                })
+                .collect::<Vec<Sound>>();
+            self.converter
+                .dictionary
+                .insert(sounds, written.to_lowercase());
+        }
+        self.converter
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::greek::{GreekScript, GreekTypographicScript};
+    use crate::official::OfficialScript;
+
+    #[test]
+    fn policies() {
+        let with = |unknown| {
+            Converter::builder(OfficialScript, GreekScript)
+                .unknown(unknown)
+                .build()
+                .convert("ala ßkota")
+        };
+        assert_eq!(with(UnknownPolicy::Keep).unwrap(), "αλά ßκοτα");
+        assert_eq!(with(UnknownPolicy::Drop).unwrap(), "αλά κοτα");
+        assert_eq!(with(UnknownPolicy::Replace('?')).unwrap(), "αλά ?κοτα");
+        assert_eq!(
+            with(UnknownPolicy::Error),
+            Err(Error::UnknownLetter {
+                offset: 4, ch: 'ß'
+            })
+        );
+
+        let converter = Converter::builder(OfficialScript, GreekScript)
+            .case(CasePolicy::Uppercase)
+            .build();
+        assert_eq!(converter.convert("Ala ma kota").unwrap(), "ΑΛΆ ΜΑ ΚΟΤΑ");
+    }
+
+    #[test]
+    fn punctuation_and_lossless() {
+        let typographic = |localize| {
+            Converter::builder(OfficialScript, GreekTypographicScript)
+                .localize_punctuation(localize)
+                .build()
+                .convert("Kto tam?")
+                .unwrap()
+        };
+        assert_eq!(typographic(true), "Κτο ταμ;");
+        assert_eq!(typographic(false), "Κτο ταμ?");
+
+        let lossless = Converter::builder(OfficialScript, GreekScript)
+            .lossless(true)
+            .build();
+        let greek = lossless.convert("kja").unwrap();
+        assert_eq!(
+            Converter::builder(GreekScript, OfficialScript)
+                .build()
+                .convert(&greek)
+                .unwrap(),
+            "kja"
+        );
+    }
+
+    #[test]
+    fn dictionary() {
+        let converter = Converter::builder(OfficialScript, GreekScript)
+            .dictionary([("Kraków", "Κρακόβ"), ("pies", "σκύλος")])
+            .build();
+        assert_eq!(
+            converter.convert("KRAKÓW, Pies i kot").unwrap(),
+            "ΚΡΑΚΌΒ, Σκύλος ί κοτ"
+        );
+    }
+
+    #[test]
+    fn aligned() {
+        let converter = Converter::builder(OfficialScript, GreekScript)
+            .pronounced(true)
+            .build();
+        let (output, alignment) = converter.convert_aligned("Bóg i chleb").unwrap();
+        assert_eq!(output, "Μπουκ ί χλέπ");
+        let last = alignment.sounds.last().unwrap();
+        assert_eq!(
+            (last.source.clone(), &output[last.output.clone()]),
+            (11..12, "π")
+        );
+    }
+
+    #[test]
+    fn stream() {
+        let converter = Converter::builder(OfficialScript, GreekScript).build();
+        let input = "ala ma kota,\nźdźbło trawy\n".repeat(1000);
+        // chunks of 7 bytes cut words and characters in the middle
+        let reader = io::BufReader::with_capacity(7, input.as_bytes());
+        let mut output = vec![];
+        converter.convert_stream(reader, &mut output).unwrap();
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            converter.convert(&input).unwrap()
+        );
+
+        let mut output = String::new();
+        let reader = io::BufReader::with_capacity(3, "radość i miód".as_bytes());
+        converter.convert_stream_fmt(reader, &mut output).unwrap();
+        assert_eq!(output, "ραδοστ' ί μόυδ");
+
+        // quotes are paired across the chunks of a line
+        let back = Converter::builder(GreekScript, OfficialScript).build();
+        let input = "'άλα μα κοτα'\nραδοστ' 'τακ'\n";
+        let mut output = String::new();
+        let reader = io::BufReader::with_capacity(4, input.as_bytes());
+        back.convert_stream_fmt(reader, &mut output).unwrap();
+        assert_eq!(output, "'jała ma kota'\nradość 'tak'\n");
+        assert_eq!(output, back.convert(input).unwrap());
+
+        let strict = Converter::builder(OfficialScript, GreekScript)
+            .unknown(UnknownPolicy::Error)
+            .build();
+        let reader = io::BufReader::with_capacity(4, "ala ma ßkota".as_bytes());
+        let error = strict.convert_stream(reader, io::sink()).unwrap_err();
+        assert_eq!(
+            error
+                .into_inner()
+                .unwrap()
+                .downcast::<Error>()
+                .unwrap()
+                .as_ref(),
+            &Error::UnknownLetter {
+                offset: 7, ch: 'ß'
+            }
+        );
+    }
+
+    #[test]
+    fn shared_between_threads() {
+        fn assert_send_sync<T: Send + Sync>() {}
+        assert_send_sync::<Converter>();
+
+        let converter = Converter::builder(OfficialScript, GreekScript).build();
+        std::thread::scope(|scope| {
+            for _ in 0..4 {
+                scope.spawn(|| assert_eq!(converter.convert("ala").unwrap(), "αλά"));
+            }
+        });
+    }
+}
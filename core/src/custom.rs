@@ -0,0 +1,571 @@
+//! Scripts described in a file, without changing the crate.
+//!
+//! A script file gives the letters of every sound and, for a script that marks softening
+//! on the letter after a consonant the way Greek and Cyrillic do, how softened consonants
+//! and the vowels after them are written. The same file is used for reading and writing:
+//!
+//! ```toml
+//! name = "grazhdanka"
+//! description = "Cyrillic without the old letters"
+//! case = true       # whether letters have upper and lower case, true if left out
+//! break = "ъ"       # keeps letters from being read together: подъзелить
+//! soft_mark = "ь"   # softening when no vowel follows: сь in сьмех
+//!
+//! [letters]         # every sound on its own, in lowercase
+//! A = "а"
+//! S = "с"
+//! Sx = "сь"
+//!
+//! [softened]        # a softened consonant before a letter that softens it: с in ся
+//! Sx = "с"
+//!
+//! [iotated]         # a vowel that softens the consonants before it, or is j + vowel: я
+//! A = "я"
+//! ```
+//!
+//! In JSON the tables are objects. Sounds are named like the variants of [`Sound`].
+
+use std::collections::BTreeMap;
+use std::fmt;
+use std::path::Path;
+
+use serde::Deserialize;
+
+use crate::official::{Text, TextRepr};
+use crate::softening::{self, Piece};
+use crate::trie::Trie;
+use crate::{CapitalisationMode, Error, Script, Sound, fold_case};
+
+/// A script read from a file, see the [module documentation](self).
+#[derive(Debug, Clone)]
+pub struct CustomScript {
+    name: String,
+    description: String,
+    case: bool,
+    break_marker: String,
+    soft_mark: String,
+    /// Indexed by [`Sound`], like the two tables below.
+    letters: Vec<Option<String>>,
+    softened: Vec<Option<String>>,
+    iotated: Vec<Option<String>>,
+    graphemes: Trie<char>,
+    /// The softened consonants as written before a letter that softens them.
+    bases: Trie<char>,
+    /// The letters that soften the consonants before them.
+    softening: Trie<char>,
+    /// Letters the reading may go on past, into the letters after them.
+    joining: Vec<char>,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct ScriptFile {
+    name: String,
+    #[serde(default)]
+    description: String,
+    #[serde(default = "has_case")]
+    case: bool,
+    #[serde(default, rename = "break")]
+    break_marker: String,
+    #[serde(default)]
+    soft_mark: String,
+    letters: BTreeMap<String, String>,
+    #[serde(default)]
+    softened: BTreeMap<String, String>,
+    #[serde(default)]
+    iotated: BTreeMap<String, String>,
+}
+
+fn has_case() -> bool {
+    true
+}
+
+impl CustomScript {
+    /// Reads a script file written in TOML.
+    pub fn from_toml(source: &str) -> Result<Self, ScriptFileError> {
+        let file = toml::from_str(source).map_err(|e| ScriptFileError(e.to_string()))?;
+        CustomScript::new(file)
+    }
+
+    /// Reads a script file written in JSON.
+    pub fn from_json(source: &str) -> Result<Self, ScriptFileError> {
+        let file = serde_json::from_str(source).map_err(|e| ScriptFileError(e.to_string()))?;
+        CustomScript::new(file)
+    }
+
+    /// Reads a script file from disk, as JSON if its name ends with `.json` and as TOML
+    /// otherwise.
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, ScriptFileError> {
+        let path = path.as_ref();
+        let in_file = |e: &dyn fmt::Display| ScriptFileError(format!("{}: {e}", path.display()));
+        let source = std::fs::read_to_string(path).map_err(|e| in_file(&e))?;
+        let script = match path.extension() {
+            Some(extension) if extension == "json" => CustomScript::from_json(&source),
+            _ => CustomScript::from_toml(&source),
+        };
+        script.map_err(|e| in_file(&e))
+    }
+
+    fn new(file: ScriptFile) -> Result<Self, ScriptFileError> {
+        let error = |message: &str| Err(ScriptFileError(message.to_string()));
+        if file.name.is_empty() {
+            return error("the script has no name");
+        }
+        let iotated = table(file.iotated)?;
+        if iotated.iter().any(Option::is_some) && file.soft_mark.is_empty() {
+            return error("a script with iotated vowels needs a soft_mark");
+        }
+        let mut script = CustomScript {
+            name: file.name,
+            description: file.description,
+            case: file.case,
+            break_marker: file.break_marker,
+            soft_mark: file.soft_mark,
+            letters: table(file.letters)?,
+            softened: table(file.softened)?,
+            iotated,
+            graphemes: Trie::new(),
+            bases: Trie::new(),
+            softening: Trie::new(),
+            joining: vec![],
+        };
+        script.graphemes = script.read_graphemes();
+        script.bases = entries(&script.softened)
+            .map(|(sound, base)| (base.chars(), vec![sound]))
+            .collect();
+        script.softening = entries(&script.iotated)
+            .map(|(_, letters)| letters)
+            .chain(Some(&*script.soft_mark))
+            .map(|letters| (letters.chars(), vec![]))
+            .collect();
+        // a whole cluster of bases is softened, and the marker is read only before a word
+        let mut joining = script
+            .graphemes
+            .inner_letters()
+            .chain(script.softening.inner_letters())
+            .chain(script.bases.letters())
+            .chain(script.break_marker.chars())
+            .collect::<Vec<_>>();
+        joining.sort_unstable();
+        joining.dedup();
+        script.joining = joining;
+        Ok(script)
+    }
+
+    /// Every grapheme the script reads. Later entries replace earlier ones, so the letters
+    /// of a sound win over an iotated vowel or a mark written the same way.
+    fn read_graphemes(&self) -> Trie<char> {
+        use Sound::*;
+        let mut trie = Trie::new();
+        for (vowel, letters) in entries(&self.iotated) {
+            let sounds = match vowel {
+                I | Y => vec![I],
+                vowel => vec![J, vowel],
+            };
+            trie.insert(letters.chars(), &sounds);
+        }
+        if !self.soft_mark.is_empty() {
+            trie.insert(self.soft_mark.chars(), &[J]);
+        }
+        if !self.break_marker.is_empty() {
+            trie.insert(self.break_marker.chars(), &[]);
+        }
+        for (sound, letters) in entries(&self.letters) {
+            trie.insert(letters.chars(), &[sound]);
+        }
+        for (consonant, base) in entries(&self.softened) {
+            for (vowel, letters) in entries(&self.iotated) {
+                // ы and и are both и after a softened consonant: only I is read from it
+                if vowel == Y && self.iotated[I as usize] == self.iotated[Y as usize] {
+                    continue;
+                }
+                // rzy: ρί
+                let vowel = if consonant == Rx && vowel == I {
+                    Y
+                } else {
+                    vowel
+                };
+                trie.insert(base.chars().chain(letters.chars()), &[consonant, vowel]);
+            }
+            trie.insert(base.chars().chain(self.soft_mark.chars()), &[consonant]);
+        }
+        trie
+    }
+
+    /// Reads a word from the start of `input`, which must be case folded if the script has
+    /// case. Returns the sounds, the letter every sound was read from, and how many letters
+    /// were read.
+    fn read(&self, input: &[char]) -> (Vec<Sound>, Vec<usize>, usize) {
+        let (mut sounds, mut origins) = (vec![], vec![]);
+        let mut i = 0;
+        while i < input.len() {
+            let rest = &input[i..];
+            let before = sounds.len();
+            let consumed = if let Some((soft, consumed)) = self.softened_in_cluster(rest) {
+                sounds.push(soft);
+                consumed
+            } else {
+                let Some((read, consumed)) = self.graphemes.longest_match(rest) else {
+                    break;
+                };
+                // the break marker only stands between letters
+                if read.is_empty() {
+                    let mut after = consumed;
+                    while let Some(([], len)) = self.graphemes.longest_match(&rest[after..]) {
+                        after += len;
+                    }
+                    if i == 0 || !self.starts_word(&rest[after..]) {
+                        break;
+                    }
+                }
+                sounds.extend_from_slice(read);
+                consumed
+            };
+            origins.extend(crate::step_origins(i, consumed, sounds.len() - before));
+            i += consumed;
+        }
+        (sounds, origins, i)
+    }
+
+    /// The first consonant of a cluster softened as a whole by the letter after it, and how
+    /// many letters it is written with. As in Greek, `r` is softened only on its own.
+    fn softened_in_cluster(&self, input: &[char]) -> Option<(Sound, usize)> {
+        let (mut first, mut cluster, mut at) = (None, 0, 0);
+        while let Some((sounds, len)) = self.bases.longest_match(&input[at..]) {
+            if sounds[0] == Sound::Rx {
+                break;
+            }
+            first.get_or_insert((sounds[0], len));
+            cluster += 1;
+            at += len;
+        }
+        let softened = cluster >= 2 && self.softening.longest_match(&input[at..]).is_some();
+        first.filter(|_| softened)
+    }
+
+    fn starts_word(&self, input: &[char]) -> bool {
+        self.graphemes
+            .longest_match(input)
+            .is_some_and(|(sounds, _)| !sounds.is_empty())
+    }
+
+    fn decode_recovering(&self, input: &str) -> (Text, Option<Error>) {
+        let case_preserving_input = input.chars().collect::<Vec<char>>();
+        let chars_input = case_preserving_input
+            .iter()
+            .map(|&c| if self.case { fold_case(c) } else { c })
+            .collect::<Vec<char>>();
+
+        let mut parts = vec![];
+        let mut error = None;
+        let mut i = 0;
+        while i < chars_input.len() {
+            if self.starts_word(&chars_input[i..]) {
+                let (sounds, origins, consumed) = self.read(&chars_input[i..]);
+                let capitalisation = CapitalisationMode::detect_per_sound(
+                    &case_preserving_input[i..i + consumed],
+                    origins.iter().copied(),
+                );
+                parts.push(TextRepr::Word(sounds, capitalisation));
+                i += consumed;
+                continue;
+            }
+
+            let start = i;
+            while i < chars_input.len() && !self.starts_word(&chars_input[i..]) {
+                if case_preserving_input[i].is_alphabetic() && error.is_none() {
+                    error = Some(Error::UnknownLetter {
+                        offset: i,
+                        ch: case_preserving_input[i],
+                    });
+                }
+                i += 1;
+            }
+            parts.push(TextRepr::Arbitrary(
+                case_preserving_input[start..i].iter().collect(),
+            ));
+        }
+        (Text { parts }, error)
+    }
+
+    fn write_recovering(&self, word: &[Sound]) -> (String, Option<Error>) {
+        let marks_softening = self.iotated.iter().any(Option::is_some);
+        fn letters(table: &[Option<String>], sound: Sound) -> Option<&str> {
+            table[sound as usize].as_deref()
+        }
+        let mut result = String::with_capacity(word.len());
+        let mut error = None;
+        // the letters before `settled` are read as the sounds before `settled_sounds`,
+        // whatever comes after them
+        let (mut settled, mut settled_sounds) = (0, 0);
+        let mut i = 0;
+        while i < word.len() {
+            let (pieces, consumed) = if marks_softening {
+                let consume_result = softening::consume(&word[i..]);
+                (consume_result.result, consume_result.consumed)
+            } else {
+                (vec![Piece::Plain(word[i])], 1)
+            };
+            let (written, written_sounds) = (result.len(), i);
+            for piece in pieces {
+                let written = match piece {
+                    Piece::Plain(s) => letters(&self.letters, s).ok_or(s),
+                    Piece::SoftBase(s) => letters(&self.softened, s)
+                        .or(letters(&self.letters, s))
+                        .ok_or(s),
+                    Piece::SoftVowel(v) => letters(&self.iotated, v)
+                        .or(letters(&self.letters, v))
+                        .ok_or(v),
+                    Piece::SoftMark => Ok(&*self.soft_mark),
+                    Piece::Glide => letters(&self.letters, Sound::J).ok_or(Sound::J),
+                    Piece::Break => Ok(&*self.break_marker),
+                };
+                match written {
+                    Ok(letters) => result.push_str(letters),
+                    Err(sound) => {
+                        let offset = word[i..i + consumed].iter().position(|&s| s == sound);
+                        error.get_or_insert(Error::UnexpectedSound {
+                            offset: i + offset.unwrap_or_default(),
+                            sound,
+                        });
+                    }
+                }
+            }
+            i += consumed;
+
+            // letters that would be read together with the ones before them
+            if written > 0 && error.is_none() && !self.break_marker.is_empty() {
+                let checked = &result[settled..];
+                let checked = checked.strip_suffix(&*self.break_marker).unwrap_or(checked);
+                let chars = checked.chars().collect::<Vec<char>>();
+                let (sounds, _, read) = self.read(&chars);
+                if read != chars.len() || sounds != word[settled_sounds..i] {
+                    result.insert_str(written, &self.break_marker);
+                    settled = written + self.break_marker.len();
+                    settled_sounds = written_sounds;
+                }
+            }
+            if result
+                .chars()
+                .next_back()
+                .is_some_and(|c| !self.joining.contains(&c))
+            {
+                (settled, settled_sounds) = (result.len(), i);
+            }
+        }
+        (result, error)
+    }
+}
+
+/// A table of the file indexed by [`Sound`].
+fn table(entries: BTreeMap<String, String>) -> Result<Vec<Option<String>>, ScriptFileError> {
+    let mut table = vec![None; Sound::ALL.len()];
+    for (sound, letters) in entries {
+        let sound = sound
+            .parse::<Sound>()
+            .map_err(|e| ScriptFileError(e.to_string()))?;
+        if letters.is_empty() {
+            return Err(ScriptFileError(format!("no letters for sound {sound}")));
+        }
+        table[sound as usize] = Some(letters);
+    }
+    Ok(table)
+}
+
+/// The sounds a table gives letters for, in the order of [`Sound::ALL`].
+fn entries(table: &[Option<String>]) -> impl Iterator<Item = (Sound, &str)> {
+    Sound::ALL
+        .into_iter()
+        .zip(table)
+        .filter_map(|(sound, letters)| Some((sound, letters.as_deref()?)))
+}
+
+impl Script for CustomScript {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn aliases(&self) -> &[&str] {
+        &[]
+    }
+
+    fn description(&self) -> &str {
+        &self.description
+    }
+
+    fn decode(&self, input: &str) -> Text {
+        self.decode_recovering(input).0
+    }
+
+    fn try_decode(&self, input: &str) -> Result<Text, Error> {
+        match self.decode_recovering(input) {
+            (text, None) => Ok(text),
+            (_, Some(error)) => Err(error),
+        }
+    }
+
+    fn encode_word(&self, sounds: &[Sound]) -> String {
+        self.write_recovering(sounds).0
+    }
+
+    fn try_encode_word(&self, sounds: &[Sound]) -> Result<String, Error> {
+        match self.write_recovering(sounds) {
+            (letters, None) => Ok(letters),
+            (_, Some(error)) => Err(error),
+        }
+    }
+
+    fn has_case(&self) -> bool {
+        self.case
+    }
+
+    fn break_marker(&self) -> &str {
+        &self.break_marker
+    }
+}
+
+/// Returned when a script file cannot be read, see [`CustomScript::load`].
+#[derive(PartialEq, Eq, Debug, Clone)]
+pub struct ScriptFileError(String);
+
+impl fmt::Display for ScriptFileError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl std::error::Error for ScriptFileError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cyrillic::CyrillicScript;
+    use crate::official::OfficialScript;
+    use crate::{convert, try_convert};
+
+    /// The built-in Cyrillic, without щ.
+    const CYRILLIC: &str = r#"
+        name = "cyrillic"
+        break = "ъ"
+        soft_mark = "ь"
+
+        [letters]
+        A = "а"
+        B = "б"
+        C = "ц"
+        Ch = "ч"
+        D = "д"
+        Dx = "дь"
+        Dz = "дз"
+        Dh = "дж"
+        E = "э"
+        Ex = "ѧ"
+        F = "ф"
+        G = "г"
+        H = "х"
+        Hx = "һ"
+        Q = "ԛ"
+        V = "ѵ"
+        X = "ӽ"
+        I = "и"
+        J = "й"
+        K = "к"
+        L = "ль"
+        Lx = "л"
+        M = "м"
+        N = "н"
+        Nx = "нь"
+        O = "о"
+        Ox = "ѫ"
+        Ou = "оу"
+        P = "п"
+        R = "р"
+        Rx = "рь"
+        S = "с"
+        Sx = "сь"
+        Sh = "ш"
+        T = "т"
+        Tx = "ть"
+        U = "у"
+        W = "в"
+        Y = "ы"
+        Z = "з"
+        Zx = "зь"
+        Zh = "ж"
+
+        [softened]
+        Sx = "с"
+        Zx = "з"
+        L = "л"
+        Tx = "т"
+        Nx = "н"
+        Rx = "р"
+        Dx = "д"
+
+        [iotated]
+        A = "я"
+        E = "е"
+        Ex = "ѩ"
+        I = "и"
+        Y = "и"
+        O = "ё"
+        Ou = "ёу"
+        Ox = "ѭ"
+        U = "ю"
+    "#;
+
+    #[test]
+    fn same_as_built_in() {
+        let custom = CustomScript::from_toml(CYRILLIC).unwrap();
+        let text = "Śnieg prószy, a Zenon je lody. Podzielić się! Rzeka, żółw, ćma, źdźbło, \
+                    dźwięk, Chrobry, Kajak i miód.";
+        let cyrillic = convert(text, &OfficialScript, &CyrillicScript);
+        assert_eq!(convert(text, &OfficialScript, &custom), cyrillic);
+        assert_eq!(convert(&cyrillic, &custom, &OfficialScript), text);
+
+        use Sound::*;
+        let podziela = [P, O, D, Zx, E, L, A];
+        assert_eq!(
+            custom.encode_word(&podziela.repeat(1000)),
+            "подъзеля".repeat(1000)
+        );
+    }
+
+    #[test]
+    fn plain_letters() {
+        let script = CustomScript::from_json(
+            r#"{
+                "name": "runes",
+                "case": false,
+                "letters": { "A": "ᚨ", "L": "ᛚ", "Lx": "ᛚᛚ", "M": "ᛗ", "K": "ᚲ", "O": "ᛟ", "T": "ᛏ" }
+            }"#,
+        )
+        .unwrap();
+        assert!(!script.has_case());
+        assert_eq!(
+            convert("Ala ma kota", &OfficialScript, &script),
+            "ᚨᛚᚨ ᛗᚨ ᚲᛟᛏᚨ"
+        );
+        assert_eq!(convert("ᛗᚨᛚᛚᛟ", &script, &OfficialScript), "mało");
+        assert_eq!(
+            try_convert("ser", &OfficialScript, &script),
+            Err(Error::UnexpectedSound {
+                offset: 0,
+                sound: Sound::S
+            })
+        );
+        assert_eq!(
+            try_convert("ᛗᚨ x", &script, &OfficialScript),
+            Err(Error::UnknownLetter { offset: 3, ch: 'x' })
+        );
+    }
+
+    #[test]
+    fn errors() {
+        let error = |source: &str| CustomScript::from_toml(source).unwrap_err().to_string();
+        assert!(error("name = \"x\"\n[letters]\nQx = \"q\"").contains("Qx"));
+        assert!(error("name = \"x\"\n[letters]\nA = \"\"").contains("no letters"));
+        assert!(error("name = \"x\"\nletters = {}\niotated = { A = \"я\" }").contains("soft_mark"));
+        assert!(error("name = \"x\"\ncolour = 1\nletters = {}").contains("colour"));
+    }
+}
@@ -0,0 +1,485 @@
+//! Polish written in Cyrillic: `поздравям теплютко`.
+//!
+//! Softening is marked the way the Greek script marks it: consonants stay hard
+//! and the vowel after them is iotated (`я`, `е`, `ё`, `ю`, `ѩ`, `ѭ`), or `ь`
+//! follows when there is no vowel. `ъ` plays the role of the Greek break.
+
+use crate::official::{Text, TextRepr};
+use crate::softening::{self, Piece};
+use crate::{CapitalisationMode, Error, Script, Sound, fold_case};
+
+/// The Cyrillic script as a [`Script`].
+#[derive(Debug, Clone, Copy, Default)]
+pub struct CyrillicScript;
+
+impl Script for CyrillicScript {
+    fn name(&self) -> &str {
+        "cyrylica"
+    }
+
+    fn aliases(&self) -> &[&str] {
+        &["c"]
+    }
+
+    fn description(&self) -> &str {
+        "Cyrillic / Cyrylica"
+    }
+
+    fn decode(&self, input: &str) -> Text {
+        utf8_cyrillic_to_text(input)
+    }
+
+    fn try_decode(&self, input: &str) -> Result<Text, Error> {
+        try_utf8_cyrillic_to_text(input)
+    }
+
+    fn encode_word(&self, sounds: &[Sound]) -> String {
+        to_cyrillic(sounds)
+    }
+
+    fn break_marker(&self) -> &str {
+        "ъ"
+    }
+}
+
+/// Writes a word given as [`Sound`]s in Cyrillic.
+pub fn to_cyrillic(input: &[Sound]) -> String {
+    let mut result = String::new();
+    // the letters before `settled` are read as the sounds before `settled_sounds`, whatever
+    // comes after them
+    let (mut settled, mut settled_sounds) = (0, 0);
+    let mut i = 0;
+    while i < input.len() {
+        let consume_result = softening::consume(&input[i..]);
+        let (written, written_sounds) = (result.len(), i);
+        for piece in consume_result.result {
+            match piece {
+                // szcz -> щ
+                Piece::Plain(Sound::Ch) if result.ends_with('ш') => {
+                    result.pop();
+                    result.push('щ');
+                }
+                Piece::Plain(s) => result.push_str(naive(s)),
+                Piece::SoftBase(s) => result.push_str(soft_base(s).unwrap_or_else(|| naive(s))),
+                Piece::SoftVowel(v) => result.push_str(soft_vowel(v).unwrap_or_else(|| naive(v))),
+                Piece::SoftMark => result.push('ь'),
+                Piece::Glide => result.push('й'),
+                Piece::Break => result.push('ъ'),
+            }
+        }
+        i += consume_result.consumed;
+
+        // letters that would be read together with the ones before them: дъз in pod|zielić
+        let chars = result[settled..].chars().collect::<Vec<char>>();
+        if written > 0 && cyrillic_word_to_sound(&chars) != input[settled_sounds..i] {
+            result.insert(written, 'ъ');
+            (settled, settled_sounds) = (written + 'ъ'.len_utf8(), written_sounds);
+        }
+        if result.chars().next_back().is_some_and(|c| !joins(c)) {
+            (settled, settled_sounds) = (result.len(), i);
+        }
+    }
+    result
+}
+
+/// Whether the reading may go on past `c`, into the letters after it: a consonant softened
+/// with the whole cluster, the first letter of дз, оу and ёу, and ш of щ.
+fn joins(c: char) -> bool {
+    softenable(c).is_some() || matches!(c, 'о' | 'ё' | 'ш')
+}
+
+fn naive(s: Sound) -> &'static str {
+    use Sound::*;
+    match s {
+        A => "а",
+        B => "б",
+        C => "ц",
+        Ch => "ч",
+        D => "д",
+        Dx => "дь",
+        Dz => "дз",
+        Dh => "дж",
+        E => "э",
+        Ex => "ѧ",
+        F => "ф",
+        G => "г",
+        H => "х",
+        Hx => "һ",
+        Q => "ԛ",
+        V => "ѵ",
+        X => "ӽ",
+        I => "и",
+        J => "й",
+        K => "к",
+        L => "ль",
+        Lx => "л",
+        M => "м",
+        N => "н",
+        Nx => "нь",
+        O => "о",
+        Ox => "ѫ",
+        Ou => "оу",
+        P => "п",
+        R => "р",
+        Rx => "рь",
+        S => "с",
+        Sx => "сь",
+        Sh => "ш",
+        T => "т",
+        Tx => "ть",
+        U => "у",
+        W => "в",
+        Y => "ы",
+        Z => "з",
+        Zx => "зь",
+        Zh => "ж",
+    }
+}
+
+/// A softened consonant, written without its softening.
+fn soft_base(s: Sound) -> Option<&'static str> {
+    use Sound::*;
+    match s {
+        Sx => Some("с"),
+        Zx => Some("з"),
+        L => Some("л"),
+        Tx => Some("т"),
+        Nx => Some("н"),
+        Rx => Some("р"),
+        Dx => Some("д"),
+        _ => None,
+    }
+}
+
+fn soft_vowel(v: Sound) -> Option<&'static str> {
+    use Sound::*;
+    match v {
+        A => Some("я"),
+        E => Some("е"),
+        Ex => Some("ѩ"),
+        I | Y => Some("и"),
+        O => Some("ё"),
+        Ou => Some("ёу"),
+        Ox => Some("ѭ"),
+        U => Some("ю"),
+        _ => None,
+    }
+}
+
+/// The hard and the softened sound of a consonant that can be softened.
+fn softenable(c: char) -> Option<(Sound, Sound)> {
+    use Sound::*;
+    match c {
+        'с' => Some((S, Sx)),
+        'з' => Some((Z, Zx)),
+        'л' => Some((Lx, L)),
+        'т' => Some((T, Tx)),
+        'н' => Some((N, Nx)),
+        'р' => Some((R, Rx)),
+        'д' => Some((D, Dx)),
+        _ => None,
+    }
+}
+
+/// The vowel of an iotated letter, which also softens the consonants before it.
+fn softening_vowel(c: char) -> Option<Sound> {
+    use Sound::*;
+    match c {
+        'я' => Some(A),
+        'е' => Some(E),
+        'ѩ' => Some(Ex),
+        'и' => Some(I),
+        'ё' => Some(O),
+        'ѭ' => Some(Ox),
+        'ю' => Some(U),
+        _ => None,
+    }
+}
+
+fn is_softening(c: char) -> bool {
+    c == 'ь' || softening_vowel(c).is_some()
+}
+
+fn naive_cyrillic_to_sound(c: char) -> Option<&'static [Sound]> {
+    use Sound::*;
+    Some(match c {
+        'а' => &[A],
+        'б' => &[B],
+        'в' => &[W],
+        'г' => &[G],
+        'д' => &[D],
+        'е' => &[J, E],
+        'ё' => &[J, O],
+        'ж' => &[Zh],
+        'з' => &[Z],
+        'и' => &[I],
+        'й' => &[J],
+        'к' => &[K],
+        'л' => &[Lx],
+        'м' => &[M],
+        'н' => &[N],
+        'о' => &[O],
+        'п' => &[P],
+        'р' => &[R],
+        'с' => &[S],
+        'т' => &[T],
+        'у' => &[U],
+        'ф' => &[F],
+        'х' => &[H],
+        'һ' => &[Hx],
+        'ԛ' => &[Q],
+        'ѵ' => &[V],
+        'ӽ' => &[X],
+        'ц' => &[C],
+        'ч' => &[Ch],
+        'ш' => &[Sh],
+        'щ' => &[Sh, Ch],
+        'ъ' => &[],
+        'ы' => &[Y],
+        'ь' => &[J],
+        'э' => &[E],
+        'ю' => &[J, U],
+        'я' => &[J, A],
+        'ѧ' => &[Ex],
+        'ѩ' => &[J, Ex],
+        'ѫ' => &[Ox],
+        'ѭ' => &[J, Ox],
+        _ => return None,
+    })
+}
+
+fn is_cyrillic_letter(c: char) -> bool {
+    naive_cyrillic_to_sound(c).is_some()
+}
+
+fn cyrillic_word_to_sound(input_initial: &[char]) -> Vec<Sound> {
+    cyrillic_word_to_sound_with_origins(input_initial).0
+}
+
+/// Like [`cyrillic_word_to_sound`], but also tells for every sound which letter it was read from.
+fn cyrillic_word_to_sound_with_origins(input_initial: &[char]) -> (Vec<Sound>, Vec<usize>) {
+    use Sound::*;
+    let mut result = vec![];
+    let mut i = 0;
+    let mut origins = vec![];
+    while i < input_initial.len() {
+        let (start, before) = (i, result.len());
+        'step: {
+            let input = &input_initial[i..];
+            let c0 = input[0];
+
+            // same rules as greek_vec_to_sound: "р" is only softened on its own
+            let mut softened_count = 0;
+            let mut was_rho = false;
+            while softened_count < input.len() {
+                let isc = input[softened_count];
+                if softenable(isc).is_none() {
+                    break;
+                }
+                if isc != 'р' && was_rho {
+                    softened_count = 0;
+                    break;
+                }
+                if isc == 'р' {
+                    was_rho = true;
+                }
+                if softened_count > 0 && was_rho {
+                    break;
+                }
+                softened_count += 1;
+            }
+
+            if softened_count > 0
+                && input.len() > softened_count
+                && is_softening(input[softened_count])
+            {
+                result.extend(
+                    input[..softened_count]
+                        .iter()
+                        .filter_map(|&c| softenable(c).map(|(_, soft)| soft)),
+                );
+                i += softened_count;
+
+                if let Some(c) = softening_vowel(input[softened_count]) {
+                    let c = if c == I && c0 == 'р' { Y } else { c };
+                    if c == O && input.get(softened_count + 1) == Some(&'у') {
+                        result.push(Ou);
+                        i += 2;
+                    } else {
+                        result.push(c);
+                        i += 1;
+                    }
+                } else {
+                    // ь, not in result
+                    i += 1;
+                }
+                break 'step;
+            }
+
+            if input.len() > 1 {
+                let digraph: Option<&[Sound]> = match (c0, input[1]) {
+                    ('д', 'з') => Some(&[Dz]),
+                    ('д', 'ж') => Some(&[Dh]),
+                    ('о', 'у') => Some(&[Ou]),
+                    ('ё', 'у') => Some(&[J, Ou]),
+                    _ => None,
+                };
+                if let Some(sounds) = digraph {
+                    result.extend_from_slice(sounds);
+                    i += 2;
+                    break 'step;
+                }
+            }
+
+            result.extend_from_slice(naive_cyrillic_to_sound(c0).unwrap_or(&[]));
+            i += 1;
+        }
+        origins.extend(crate::step_origins(start, i - start, result.len() - before));
+    }
+    (result, origins)
+}
+
+/// Parses text written in Cyrillic into the intermediate representation.
+///
+/// Never fails: letters that cannot be read are kept in place as [`TextRepr::Arbitrary`].
+pub fn utf8_cyrillic_to_text(input: &str) -> Text {
+    utf8_cyrillic_to_text_recovering(input).0
+}
+
+/// Like [`utf8_cyrillic_to_text`], but fails on the first letter that cannot be read.
+pub fn try_utf8_cyrillic_to_text(input: &str) -> Result<Text, Error> {
+    match utf8_cyrillic_to_text_recovering(input) {
+        (text, None) => Ok(text),
+        (_, Some(error)) => Err(error),
+    }
+}
+
+fn utf8_cyrillic_to_text_recovering(input: &str) -> (Text, Option<Error>) {
+    let case_preserving_input = input.chars().collect::<Vec<char>>();
+    let chars_input: Vec<char> = case_preserving_input
+        .iter()
+        .map(|&c| fold_case(c))
+        .collect();
+
+    let mut parts = vec![];
+    let mut error = None;
+    let mut i = 0;
+
+    while i < chars_input.len() {
+        let chars = &chars_input[i..];
+
+        let word_len = chars.iter().take_while(|&&c| is_cyrillic_letter(c)).count();
+        if word_len > 0 {
+            // ъ splits the word in two, like the Greek break
+            let (mut start, mut previous) = (0, 0);
+            for end in 1..=word_len {
+                if end == word_len || chars[end] == 'ъ' {
+                    // ъ ending a word marks a word of one capital letter as written in capitals
+                    if start > 0
+                        && end == word_len
+                        && chars[start..end] == ['ъ']
+                        && let Some(TextRepr::Word(_, case)) = parts.last_mut()
+                        && case.read_capitals_mark(&case_preserving_input[i + previous..i + start])
+                    {
+                        break;
+                    }
+                    let (sounds, origins) = cyrillic_word_to_sound_with_origins(&chars[start..end]);
+                    parts.push(TextRepr::Word(
+                        sounds,
+                        CapitalisationMode::detect_per_sound(
+                            &case_preserving_input[i + start..i + end],
+                            origins.iter().copied(),
+                        ),
+                    ));
+                    (previous, start) = (start, end);
+                }
+            }
+            i += word_len;
+            continue;
+        }
+
+        let mut j = 0;
+        while j < chars.len() && !is_cyrillic_letter(chars[j]) {
+            if chars[j].is_alphabetic() && error.is_none() {
+                error = Some(Error::UnknownLetter {
+                    offset: i + j,
+                    ch: case_preserving_input[i + j],
+                });
+            }
+            j += 1;
+        }
+
+        parts.push(TextRepr::Arbitrary(
+            case_preserving_input[i..i + j].iter().collect(),
+        ));
+        i += j;
+    }
+
+    (Text { parts }, error)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::convert;
+    use crate::official::OfficialScript;
+
+    use super::*;
+
+    fn assert_both_ways(official: &str, cyrillic: &str) {
+        assert_eq!(
+            convert(official, &OfficialScript, &CyrillicScript),
+            cyrillic
+        );
+        assert_eq!(
+            convert(cyrillic, &CyrillicScript, &OfficialScript),
+            official
+        );
+    }
+
+    #[test]
+    fn official_cyrillic_both_ways() {
+        assert_both_ways("pozdrawiam cieplutko!", "поздравям теплютко!");
+        assert_both_ways(
+            "Litwo, ojczyzno moja! Ty jesteś jak zdrowie!",
+            "Литво, ойчызно моя! Ты естэсь як здрове!",
+        );
+        assert_both_ways("szczęście", "щѧсте");
+        assert_both_ways("przyjdzie kryska na matyska", "прийде крыска на матыска");
+        assert_both_ways("zjem", "зъем");
+        assert_both_ways("miód i dziób", "мёуд и дёуб");
+        assert_both_ways("rzeka", "река");
+        assert_both_ways("ZIEMIA", "ЗЕМЯ");
+        assert_both_ways("McDonald i iPhone", "МцДональд и иПһонэ");
+        assert_both_ways("herbata i chleb", "һэрбата и хлеб");
+        assert_both_ways("Volvo, taxi, aquapark", "Ѵольѵо, таӽи, аԛуапарк");
+        assert_both_ways("podzielić i odżywka", "подъзелить и одъжывка");
+
+        use Sound::*;
+        let podzielic = [P, O, D, Zx, E, L, I, Tx];
+        assert_eq!(
+            to_cyrillic(&podzielic.repeat(1000)),
+            "подъзелить".repeat(1000)
+        );
+    }
+
+    #[test]
+    fn word_to_sound() {
+        use Sound::*;
+        assert_eq!(
+            cyrillic_word_to_sound(&"щѧсте".chars().collect::<Vec<_>>()),
+            [Sh, Ch, Ex, Sx, Tx, E]
+        );
+        assert_eq!(
+            cyrillic_word_to_sound(&"дзвон".chars().collect::<Vec<_>>()),
+            [Dz, W, O, N]
+        );
+    }
+
+    #[test]
+    fn unknown_letters() {
+        assert_eq!(
+            try_utf8_cyrillic_to_text("мама, tata"),
+            Err(Error::UnknownLetter { offset: 6, ch: 't' })
+        );
+    }
+}
@@ -0,0 +1,38 @@
+use std::fmt;
+
+use crate::Sound;
+
+/// Why a strict (`try_*`) conversion failed.
+#[derive(PartialEq, Eq, Debug, Clone)]
+pub enum Error {
+    /// A letter the source script cannot read.
+    UnknownLetter {
+        /// Position of the letter in the input, counted in `char`s.
+        offset: usize,
+        ch: char,
+    },
+    /// A sound the target script cannot write.
+    UnexpectedSound {
+        /// Position of the sound in the word, counted in [`Sound`]s.
+        offset: usize,
+        sound: Sound,
+    },
+    /// No script with the given name is registered.
+    UnknownScript(String),
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::UnknownLetter { offset, ch } => {
+                write!(f, "unknown letter {ch:?} at index {offset}")
+            }
+            Error::UnexpectedSound { offset, sound } => {
+                write!(f, "cannot write sound {sound} at index {offset}")
+            }
+            Error::UnknownScript(name) => write!(f, "unknown script {name:?}"),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
@@ -1,193 +1,659 @@
-struct ConsumeResult {
-    result: Vec<Greek>,
-    consumed: usize,
-}
+//! The Greek script for Polish: `ποζδραβάμ τέπλύτκο`.
+
+use std::borrow::Cow;
+use std::fmt;
+use std::str::FromStr;
+use std::sync::LazyLock;
 
-use crate::CapitalisationMode;
+use crate::rules::Rules;
+use crate::trie::Trie;
+use crate::{CapitalisationMode, Error, Script, fold_case};
 
 use super::Sound;
 
-fn do_the_job(input: &[Sound]) -> Vec<Greek> {
-    let mut result = vec![];
-    let mut i = 0;
+/// A way of writing the Greek script, for the choices where the usual one is only one of
+/// several that work. [`Variant::default`] is the script of [`GreekScript`].
+///
+/// A variant is named by its choices that differ from the default, joined with `+`:
+///
+/// ```
+/// use beciarz_core::greek::{GreekVariantScript, Sibilants, Variant};
+/// use beciarz_core::{convert, official::OfficialScript};
+///
+/// let variant: Variant = "hacek+beta".parse().unwrap();
+/// assert_eq!(variant.sibilants, Sibilants::Caron);
+/// let greek = GreekVariantScript::new(variant);
+/// assert_eq!(convert("szczebrzeszyn", &OfficialScript, &greek), "σ̌τσ̌εβρέσ̌ιν");
+/// assert_eq!(convert("σ̌τσ̌εβρέσ̌ιν", &greek, &OfficialScript), "szczebrzeszyn");
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub struct Variant {
+    pub sibilants: Sibilants,
+    pub labials: Labials,
+    pub breaks: BreakMark,
+    /// Whether softening is carried through `m`, `p`, `b`, `w` and `f` to the letter after
+    /// them: `σμέψνε` rather than `σ'μέψνε` for śmieszne. Named `soft-labials`.
+    pub soft_labials: bool,
+}
+
+/// How `cz`, `sz`, `ż` and `dż` are written.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum Sibilants {
+    /// `θ`, `ψ`, `ξ` and `δξ`.
+    #[default]
+    Letters,
+    /// With a háček: `τσ̌`, `σ̌`, `ζ̌` and `δζ̌`. Named `hacek`.
+    Caron,
+}
+
+/// How `b`, `w` and `u` are written.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum Labials {
+    /// `μπ`, `β` and `υ`, the way modern Greek reads them.
+    #[default]
+    MuPi,
+    /// `β`, `υ` and `ϋ`: `βαυί` for bawi, `βϋκ` for buk. Named `beta`.
+    Beta,
+}
+
+/// What keeps letters from being read together: `ποδ\ζέλίτ'` for podzielić.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum BreakMark {
+    /// A backslash.
+    #[default]
+    Backslash,
+    /// A combining diaeresis on the letter before the break, or a backslash at the start
+    /// of a word, where there is no letter to put it on. Named `diaeresis`. Not with
+    /// [`Labials::Beta`], where `υ` with a diaeresis is the letter `ϋ`.
+    Diaeresis,
+    /// The invisible zero width non-joiner, U+200C. Named `zwnj`.
+    Zwnj,
+}
 
-    while i < input.len() {
-        let consume_result = consume_greek(&input[i..]);
-        result.extend(consume_result.result);
-        i += consume_result.consumed;
+impl Variant {
+    /// Writes [`Greek::Break`].
+    pub fn break_marker(self) -> &'static str {
+        match self.breaks {
+            BreakMark::Backslash => "\\",
+            BreakMark::Diaeresis => "\u{308}",
+            BreakMark::Zwnj => "\u{200c}",
+        }
     }
 
-    result
+    /// The names of the choices that differ from the default.
+    fn names(self) -> impl Iterator<Item = &'static str> {
+        [
+            (self.sibilants == Sibilants::Caron, "hacek"),
+            (self.labials == Labials::Beta, "beta"),
+            (self.breaks == BreakMark::Diaeresis, "diaeresis"),
+            (self.breaks == BreakMark::Zwnj, "zwnj"),
+            (self.soft_labials, "soft-labials"),
+        ]
+        .into_iter()
+        .filter_map(|(chosen, name)| chosen.then_some(name))
+    }
+
+    /// Every letter and group of letters the variant reads in a scheme, see [`V1_LETTERS`].
+    fn letters(self, scheme: Scheme) -> Vec<(&'static [Greek], &'static [Sound])> {
+        match scheme {
+            Scheme::V1 => {
+                let sibilants = match self.sibilants {
+                    Sibilants::Letters => V1_SIBILANT_LETTERS,
+                    Sibilants::Caron => V1_SIBILANT_CARONS,
+                };
+                let labials = match self.labials {
+                    Labials::MuPi => V1_LABIALS_MU_PI,
+                    Labials::Beta => V1_LABIALS_BETA,
+                };
+                [V1_LETTERS, sibilants, labials].concat()
+            }
+        }
+    }
+
+    /// The rules the variant is written with in a scheme: its own, then those of the default.
+    fn rules(self, scheme: Scheme) -> String {
+        let [default, caron, beta, soft_labials] = match scheme {
+            Scheme::V1 => [
+                include_str!("../rules/greek-v1/greek.rules"),
+                include_str!("../rules/greek-v1/caron.rules"),
+                include_str!("../rules/greek-v1/beta.rules"),
+                include_str!("../rules/greek-v1/soft-labials.rules"),
+            ],
+        };
+        let mut rules = String::new();
+        if self.sibilants == Sibilants::Caron {
+            rules.push_str(caron);
+        }
+        if self.labials == Labials::Beta {
+            rules.push_str(beta);
+        }
+        if self.soft_labials {
+            rules.push_str(soft_labials);
+        }
+        rules.push_str(default);
+        rules
+    }
 }
 
-pub fn to_greek(input: &[Sound]) -> String {
-    let greek_chars = do_the_job(input);
-    greek_chars.iter().map(|&g| to_char(g)).collect()
+impl fmt::Display for Variant {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (k, name) in self.names().enumerate() {
+            if k > 0 {
+                f.write_str("+")?;
+            }
+            f.write_str(name)?;
+        }
+        Ok(())
+    }
 }
 
-fn consume_naive(input: Sound) -> &'static [Greek] {
-    use Greek::*;
-    match input {
-        Sound::A => &[Alpha],
-        Sound::B => &[Mu, Pi],
-        Sound::C => &[Tau, Sigma],
-        Sound::Ch => &[Theta],
-        Sound::D => &[Delta],
-        Sound::Dx => &[Delta, Acute],
-        Sound::E => &[Epsilon],
-        Sound::Ex => &[Eta],
-        Sound::F => &[Phi],
-        Sound::G => &[Gamma],
-        Sound::H => &[Chi],
-        Sound::I => &[IotaAcute],
-        Sound::J => &[Acute],
-        Sound::K => &[Kappa],
-        Sound::L => &[Lambda, Acute],
-        Sound::Lx => &[Lambda],
-        Sound::M => &[Mu],
-        Sound::N => &[Nu],
-        Sound::Nx => &[Nu, Acute],
-        Sound::O => &[Omicron],
-        Sound::Ox => &[Omega],
-        Sound::Ou => &[Omicron, Upsilon],
-        Sound::P => &[Pi],
-        Sound::R => &[Rho],
-        Sound::S => &[Sigma],
-        Sound::Sx => &[Sigma, Acute],
-        Sound::Sh => &[Psi],
-        Sound::T => &[Tau],
-        Sound::Tx => &[Tau, Acute],
-        Sound::U => &[Upsilon],
-        Sound::W => &[Beta],
-        Sound::Y => &[Iota],
-        Sound::Z => &[Zeta],
-        Sound::Zx => &[Zeta, Acute],
-        Sound::Zh => &[Xi],
-        Sound::Rx => &[Rho, Acute],
-        Sound::Dz => &[Delta, Zeta],
-        Sound::Dh => &[Delta, Xi],
-    }
-}
-
-fn softened_sound_to_base_greek(s: Sound) -> Greek {
-    use Greek::*;
-    use Sound::*;
-    match s {
-        Sx => Sigma,
-        Zx => Zeta,
-        L => Lambda,
-        Tx => Tau,
-        Nx => Nu,
-        I => Iota,
-        Rx => Rho,
-        Dx => Delta,
-        _ => panic!("Unexpected softened sound: {s:?}"),
+impl FromStr for Variant {
+    type Err = ParseVariantError;
+
+    /// Reads the names of choices joined with `+`. An empty string is the default.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut variant = Variant::default();
+        for name in s.split('+').filter(|name| !name.is_empty()) {
+            match name {
+                "hacek" => variant.sibilants = Sibilants::Caron,
+                "beta" => variant.labials = Labials::Beta,
+                "diaeresis" => variant.breaks = BreakMark::Diaeresis,
+                "zwnj" => variant.breaks = BreakMark::Zwnj,
+                "soft-labials" => variant.soft_labials = true,
+                _ => {
+                    return Err(ParseVariantError(format!(
+                        "unknown Greek variant: {name:?}"
+                    )));
+                }
+            }
+        }
+        if variant.labials == Labials::Beta && variant.breaks == BreakMark::Diaeresis {
+            return Err(ParseVariantError(
+                "beta reads υ with a diaeresis as ϋ, so it cannot break with one".to_string(),
+            ));
+        }
+        Ok(variant)
     }
 }
 
-fn soften_vowel(sound: Sound) -> &'static [Greek] {
-    use Greek::*;
-    use Sound::*;
-    match sound {
-        A => &[AlphaAcute],
-        E => &[EpsilonAcute],
-        Ex => &[EtaAcute],
-        I => &[IotaAcute],
-        O => &[OmicronAcute],
-        Ou => &[OmicronAcute, Upsilon],
-        Ox => &[OmegaAcute],
-        U => &[UpsilonAcute],
-        _ => panic!("Unexpected vowel for softening: {sound:?}"),
-    }
-}
-
-fn consume_greek(input: &[Sound]) -> ConsumeResult {
-    if input.is_empty() {
-        return ConsumeResult {
-            result: vec![],
-            consumed: 0,
-        };
+/// Returned when a string does not name a [`Variant`].
+#[derive(PartialEq, Eq, Debug, Clone)]
+pub struct ParseVariantError(String);
+
+impl fmt::Display for ParseVariantError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
     }
+}
 
-    let i0 = input[0];
+impl std::error::Error for ParseVariantError {}
+
+/// A version of the tables of the Greek script. Text written with one version is read the
+/// same way by every release that has it, so a version is never changed, only followed by
+/// a new one. [`Scheme::default`] is the newest, used by [`GreekScript`].
+///
+/// The tables of [`Scheme::V1`] are the `V1_` letters here and the rules in
+/// `core/rules/greek-v1`. `core/schemes/greek-v1.txt` holds them for every variant, and
+/// `core/schemes/greek-v1.json` for the default one, see [`GreekVariantScript::tables_json`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+#[non_exhaustive]
+pub enum Scheme {
+    #[default]
+    V1,
+}
 
-    if input.len() == 1 {
-        return ConsumeResult {
-            result: consume_naive(i0).to_vec(),
-            consumed: 1,
-        };
+impl fmt::Display for Scheme {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Scheme::V1 => f.write_str("v1"),
+        }
     }
+}
 
-    let mut softened_count = 0;
-    while softened_count < input.len()
-        && input[softened_count].is_softened()
-        && input[softened_count] != Sound::I
-    {
-        softened_count += 1;
+impl FromStr for Scheme {
+    type Err = ParseSchemeError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "v1" => Ok(Scheme::V1),
+            _ => Err(ParseSchemeError(s.to_string())),
+        }
     }
+}
 
-    if softened_count > 0 {
-        let following_vowel = input.get(softened_count).cloned().filter(|x| x.is_vowel());
-        let total_consumed = softened_count + following_vowel.map_or(0, |_| 1);
+/// Returned when a string does not name a [`Scheme`].
+#[derive(PartialEq, Eq, Debug, Clone)]
+pub struct ParseSchemeError(String);
 
-        let last_greek: &[Greek] = match following_vowel {
-            Some(Y) => &[Greek::IotaAcute], // SPECIAL CASE FOR "RZY", TODO MAKE SURE "RZ" IS PRESENT
-            Some(vowel) => soften_vowel(vowel),
-            None => &[Greek::Acute],
-        };
+impl fmt::Display for ParseSchemeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "unknown scheme: {:?}", self.0)
+    }
+}
 
-        let mut result = input[..softened_count]
+impl std::error::Error for ParseSchemeError {}
+
+/// How a [`Variant`] writes and reads Polish sounds in a [`Scheme`].
+#[derive(Debug, Clone)]
+struct Tables {
+    variant: Variant,
+    scheme: Scheme,
+    writing: Rules,
+    graphemes: Trie<Greek>,
+    /// Letters the reading may go on past, into the letters after them: `ο` of `ου`, `σ` of
+    /// `σνέ`, and with [`Variant::soft_labials`] the labials.
+    joining: Vec<Greek>,
+    /// Letters read as a vowel, alone or after `j`: `υ`, but `ϋ` with [`Labials::Beta`],
+    /// where `υ` is `w`.
+    vowels: Vec<Greek>,
+}
+
+static DEFAULT: LazyLock<Tables> =
+    LazyLock::new(|| Tables::new(Variant::default(), Scheme::default()));
+
+impl Tables {
+    fn new(variant: Variant, scheme: Scheme) -> Self {
+        let reading = reading(variant, scheme);
+        let graphemes: Trie<Greek> = reading.iter().cloned().collect();
+        let mut joining = graphemes.inner_letters().collect::<Vec<_>>();
+        for (letters, sounds) in &reading {
+            let labial = matches!(
+                sounds[..],
+                [Sound::M | Sound::P | Sound::B | Sound::W | Sound::F]
+            );
+            joining.extend(
+                letters
+                    .iter()
+                    .filter(|g| g.can_be_softened() || (variant.soft_labials && labial)),
+            );
+        }
+        let vowels = reading
             .iter()
-            .map(|&s| softened_sound_to_base_greek(s))
-            .collect::<Vec<Greek>>();
+            .filter(|(letters, _)| letters.len() == 1)
+            .map(|(letters, _)| letters[0])
+            .filter(|&letter| {
+                graphemes
+                    .longest_match(&[letter])
+                    .is_some_and(|(sounds, _)| sounds.last().is_some_and(|s| s.is_vowel()))
+            })
+            .collect();
+        Tables {
+            variant,
+            scheme,
+            writing: Rules::parse(&variant.rules(scheme)).expect("the Greek rules are valid"),
+            graphemes,
+            joining,
+            vowels,
+        }
+    }
+
+    fn write(&self, input: &[Sound]) -> Vec<Greek> {
+        let mut result = Vec::with_capacity(input.len() + 2);
+        // where the letters read together start: σνά is read as a whole
+        let (mut group, mut group_sounds) = (0, 0);
+        // the letters before `settled` are read as the sounds before `settled_sounds`,
+        // whatever comes after them
+        let (mut settled, mut settled_sounds) = (0, 0);
+        let mut i = 0;
+
+        while i < input.len() {
+            let Some(step) = self.writing.step(input, i) else {
+                i += 1;
+                continue;
+            };
+            result.extend(step.letters.chars().filter_map(char_to_greek));
+            i += step.consumed;
+            if step.lookahead {
+                continue;
+            }
+
+            // letters that would be read together with the ones before them: δ\ζ in pod|zielić
+            if group > 0 && !self.reads_as(&result[settled..], &input[settled_sounds..i]) {
+                result.insert(group, Greek::Break);
+                (settled, settled_sounds) = (group + 1, group_sounds);
+            }
+            if result.last().is_some_and(|g| !self.joining.contains(g)) {
+                (settled, settled_sounds) = (result.len(), i);
+            }
+            (group, group_sounds) = (result.len(), i);
+        }
+
+        // ' starting a word before a consonant would be read as a quotation mark
+        if result.first() == Some(&Greek::Acute)
+            && !result.get(1).is_some_and(|g| self.vowels.contains(g))
+        {
+            result.insert(0, Greek::Break);
+        }
+        result
+    }
+
+    fn write_string(&self, input: &[Sound]) -> String {
+        let mut result = String::with_capacity(input.len() * 2);
+        self.write_into(input, &mut result);
+        result
+    }
+
+    fn write_into(&self, input: &[Sound], output: &mut String) {
+        let letters = self.write(input);
+        match letters.split_first() {
+            // a diaeresis has no letter to go on at the start of a word
+            Some((Greek::Break, rest)) if self.variant.breaks == BreakMark::Diaeresis => {
+                output.push('\\');
+                self.spell_into(rest.iter().copied(), output);
+            }
+            _ => self.spell_into(letters, output),
+        }
+    }
+
+    #[cfg(any(test, feature = "json"))]
+    fn spell(&self, letters: impl IntoIterator<Item = Greek>) -> String {
+        let mut result = String::new();
+        self.spell_into(letters, &mut result);
+        result
+    }
+
+    fn spell_into(&self, letters: impl IntoIterator<Item = Greek>, output: &mut String) {
+        for greek in letters {
+            match greek {
+                Greek::Break => output.push_str(self.variant.break_marker()),
+                greek => output.push(to_char(greek)),
+            }
+        }
+    }
+
+    #[cfg(feature = "json")]
+    fn to_json(&self) -> serde_json::Value {
+        let reading = reading(self.variant, self.scheme)
+            .into_iter()
+            .map(|(letters, sounds)| {
+                let sounds = sounds.iter().map(Sound::to_string).collect::<Vec<String>>();
+                (self.spell(letters), sounds)
+            })
+            .collect::<std::collections::BTreeMap<String, Vec<String>>>();
+        serde_json::json!({
+            "scheme": self.scheme.to_string(),
+            "variant": self.variant.to_string(),
+            "break": self.variant.break_marker(),
+            "reading": reading,
+            "writing": self
+                .writing
+                .to_json(|letters| self.spell(letters.chars().filter_map(char_to_greek))),
+        })
+    }
+}
 
-        result.extend_from_slice(last_greek);
+/// Every letter and group of letters a variant reads in a scheme, with its sounds. Of two
+/// equal groups, the later one is read.
+fn reading(variant: Variant, scheme: Scheme) -> Vec<(Vec<Greek>, Vec<Sound>)> {
+    let letters = variant.letters(scheme);
+    let softened = letters.iter().flat_map(|&(consonant, _)| {
+        let soft = consonant[0]
+            .softened_sound()
+            .filter(|_| consonant.len() == 1);
+        soft.into_iter()
+            .flat_map(|soft| softened(consonant[0], soft, &letters))
+    });
+    softened
+        .chain(
+            letters
+                .iter()
+                .map(|&(letters, sounds)| (letters.to_vec(), sounds.to_vec())),
+        )
+        .collect()
+}
+
+/// The Greek script as a [`Script`], in the newest [`Scheme`].
+#[derive(Debug, Clone, Copy, Default)]
+pub struct GreekScript;
+
+impl Script for GreekScript {
+    fn name(&self) -> &str {
+        "grecki"
+    }
+
+    fn aliases(&self) -> &[&str] {
+        &["g"]
+    }
+
+    fn description(&self) -> &str {
+        "Greek / Grecki"
+    }
+
+    fn decode(&self, input: &str) -> super::official::Text {
+        utf8_greek_to_text(input)
+    }
+
+    fn try_decode(&self, input: &str) -> Result<super::official::Text, Error> {
+        try_utf8_greek_to_text(input)
+    }
 
-        return ConsumeResult {
-            result,
-            consumed: total_consumed,
+    fn encode_word(&self, sounds: &[Sound]) -> String {
+        to_greek(sounds)
+    }
+
+    fn encode_word_into(&self, sounds: &[Sound], output: &mut String) {
+        DEFAULT.write_into(sounds, output)
+    }
+
+    fn break_marker(&self) -> &str {
+        "\\"
+    }
+
+    fn escape<'a>(&self, arbitrary: &'a str) -> Cow<'a, str> {
+        escape(arbitrary)
+    }
+}
+
+/// The Greek script written in a [`Variant`] with the tables of a [`Scheme`], named
+/// `grecki`, `+` and the name of the variant if it is not the default, then `@` and the
+/// scheme: `grecki+hacek@v1`.
+#[derive(Debug, Clone)]
+pub struct GreekVariantScript {
+    name: String,
+    description: String,
+    tables: Tables,
+}
+
+impl GreekVariantScript {
+    /// The variant in the newest scheme, which may change with a new release.
+    pub fn new(variant: Variant) -> Self {
+        GreekVariantScript::with_scheme(variant, Scheme::default())
+    }
+
+    /// The variant in the given scheme, written and read the same way by every release.
+    pub fn with_scheme(variant: Variant, scheme: Scheme) -> Self {
+        let choices = variant
+            .names()
+            .map(str::to_string)
+            .chain([scheme.to_string()])
+            .collect::<Vec<String>>()
+            .join(", ");
+        let name = match variant == Variant::default() {
+            true => format!("grecki@{scheme}"),
+            false => format!("grecki+{variant}@{scheme}"),
         };
+        GreekVariantScript {
+            name,
+            description: format!("Greek, {choices} / Grecki, {choices}"),
+            tables: Tables::new(variant, scheme),
+        }
     }
 
-    use Sound::*;
-    if i0 == Sound::J {
-        let i1 = *input.get(1).unwrap();
-        if i1.is_vowel() && i1 != Y && i1 != I {
-            return ConsumeResult {
-                result: soften_vowel(i1).to_vec(),
-                consumed: 2,
-            };
-            // TODO Case for I Y
-        } else {
-            return ConsumeResult {
-                result: vec![Greek::Acute],
-                consumed: 1,
-            };
+    pub fn variant(&self) -> Variant {
+        self.tables.variant
+    }
+
+    pub fn scheme(&self) -> Scheme {
+        self.tables.scheme
+    }
+
+    /// The tables the script is written and read with, as JSON, for other implementations
+    /// of the same scheme:
+    ///
+    /// - `reading`: every letter and group of letters with the sounds it is read as, always
+    ///   taking the longest group that matches. Besides, the first of two or more of
+    ///   `σ ζ λ ν τ δ` in a row is read softened when the last of them is: `σ` in `σνέκ`.
+    ///   With `soft-labials`, one of them is also read softened before labials followed by
+    ///   a vowel with an acute: `σ` in `σμέ`.
+    /// - `writing`: the rules of [`crate::rules`], every class spelled out as its sounds.
+    ///   A word is written by the first rule that matches at each sound; where the written
+    ///   letters would be read as other sounds, the `break` mark is put before them.
+    ///
+    /// Sounds are named as in [`Sound`].
+    #[cfg(feature = "json")]
+    pub fn tables_json(&self) -> String {
+        serde_json::to_string_pretty(&self.tables.to_json()).expect("the tables are valid JSON")
+    }
+}
+
+impl Script for GreekVariantScript {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn aliases(&self) -> &[&str] {
+        &[]
+    }
+
+    fn description(&self) -> &str {
+        &self.description
+    }
+
+    fn decode(&self, input: &str) -> super::official::Text {
+        utf8_greek_to_text_recovering(&self.tables, input).0
+    }
+
+    fn try_decode(&self, input: &str) -> Result<super::official::Text, Error> {
+        match utf8_greek_to_text_recovering(&self.tables, input) {
+            (text, None) => Ok(text),
+            (_, Some(error)) => Err(error),
         }
     }
 
-    // check for break
-    let i1 = *input.get(1).unwrap();
-    if i1 == J || i1 == I {
-        //|| i1 == Nx || i1 == Zx || i1 == Sx || i1 == Tx  || i1 == Dx ||i1==Rx{
-        if i0 == N || i0 == Z || i0 == S || i0 == T || i0 == D || i0 == R {
-            let mut r0 = consume_naive(i0).to_vec();
-            r0.push(Greek::Break);
-            return ConsumeResult {
-                result: r0,
-                consumed: 1,
-            };
+    fn encode_word(&self, sounds: &[Sound]) -> String {
+        self.tables.write_string(sounds)
+    }
+
+    fn encode_word_into(&self, sounds: &[Sound], output: &mut String) {
+        self.tables.write_into(sounds, output)
+    }
+
+    fn break_marker(&self) -> &str {
+        self.tables.variant.break_marker()
+    }
+
+    fn starts_with_break(&self, word: &str) -> bool {
+        word.chars().next().and_then(char_to_greek) == Some(Greek::Break)
+    }
+
+    fn escape<'a>(&self, arbitrary: &'a str) -> Cow<'a, str> {
+        escape(arbitrary)
+    }
+}
+
+/// The Greek script typeset the way Greek is: final `ς`, `;` for a question mark
+/// and `«»` for quotes.
+///
+/// Reading accepts everything [`GreekScript`] does, and turns the punctuation back.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct GreekTypographicScript;
+
+impl Script for GreekTypographicScript {
+    fn name(&self) -> &str {
+        "grecki-typograficzny"
+    }
+
+    fn aliases(&self) -> &[&str] {
+        &["gt"]
+    }
+
+    fn description(&self) -> &str {
+        "Greek, typographic / Grecki typograficzny"
+    }
+
+    fn decode(&self, input: &str) -> super::official::Text {
+        delocalize_punctuation(utf8_greek_to_text(input))
+    }
+
+    fn try_decode(&self, input: &str) -> Result<super::official::Text, Error> {
+        try_utf8_greek_to_text(input).map(delocalize_punctuation)
+    }
+
+    fn encode_word(&self, sounds: &[Sound]) -> String {
+        to_greek_typographic(sounds)
+    }
+
+    fn encode_word_into(&self, sounds: &[Sound], output: &mut String) {
+        let start = output.len();
+        DEFAULT.write_into(sounds, output);
+        if output.len() > start && output.ends_with('σ') {
+            output.pop();
+            output.push('ς');
+        }
+    }
+
+    fn break_marker(&self) -> &str {
+        "\\"
+    }
+
+    fn escape<'a>(&self, arbitrary: &'a str) -> Cow<'a, str> {
+        escape(arbitrary)
+    }
+
+    fn localize_punctuation<'a>(&self, arbitrary: &'a str) -> Cow<'a, str> {
+        if !arbitrary.contains(['?', ';', '„', '”']) {
+            return Cow::Borrowed(arbitrary);
         }
+        arbitrary
+            .chars()
+            .map(|c| match c {
+                '?' => ';',
+                ';' => '·',
+                '„' => '«',
+                '”' => '»',
+                c => c,
+            })
+            .collect::<String>()
+            .into()
     }
+}
 
-    ConsumeResult {
-        result: consume_naive(i0).to_vec(),
-        consumed: 1,
+fn delocalize_punctuation(mut text: super::official::Text) -> super::official::Text {
+    for part in &mut text.parts {
+        if let super::official::TextRepr::Arbitrary(arbitrary) = part {
+            *arbitrary = arbitrary
+                .chars()
+                .map(|c| match c {
+                    // ; and · may also come as the Greek question mark and ano teleia
+                    ';' | '\u{37e}' => '?',
+                    '·' | '\u{387}' => ';',
+                    '«' => '„',
+                    '»' => '”',
+                    c => c,
+                })
+                .collect();
+        }
     }
+    text
 }
 
-#[derive(PartialEq, Eq, Debug, Clone, Copy)]
-pub enum Greek {
+/// Writes a word given as [`Sound`]s in the Greek script.
+pub fn to_greek(input: &[Sound]) -> String {
+    DEFAULT.write_string(input)
+}
+
+/// Like [`to_greek`], but with `ς` at the end of the word.
+pub fn to_greek_typographic(input: &[Sound]) -> String {
+    let mut result = to_greek(input);
+    if result.ends_with('σ') {
+        result.pop();
+        result.push('ς');
+    }
+    result
+}
+
+#[derive(PartialEq, Eq, PartialOrd, Ord, Debug, Clone, Copy)]
+pub(crate) enum Greek {
     Alpha,
     AlphaAcute,
     Beta,
@@ -214,33 +680,56 @@ pub enum Greek {
     Tau,
     Upsilon,
     UpsilonAcute,
+    UpsilonDiaeresis,
     Phi,
     Chi,
+    Heta,
     Psi,
+    Digamma,
+    Koppa,
+    Kai,
     Omega,
     OmegaAcute,
     Acute,
+    /// A combining háček: `σ̌`.
+    Caron,
     Break,
 }
 
 impl Greek {
     fn is_softening(self) -> bool {
-        matches!(
-            self,
-            Greek::Acute
-                | Greek::AlphaAcute
-                | Greek::EpsilonAcute
-                | Greek::EtaAcute
-                | Greek::IotaAcute
-                | Greek::OmicronAcute
-                | Greek::UpsilonAcute
-                | Greek::OmegaAcute
-        )
+        self == Greek::Acute || self.softening_vowel().is_some()
+    }
+
+    /// The vowel written with an acute, which also softens the preceding consonants.
+    fn softening_vowel(self) -> Option<Sound> {
+        match self {
+            Greek::AlphaAcute => Some(Sound::A),
+            Greek::EpsilonAcute => Some(Sound::E),
+            Greek::EtaAcute => Some(Sound::Ex),
+            Greek::IotaAcute => Some(Sound::I),
+            Greek::OmicronAcute => Some(Sound::O),
+            Greek::UpsilonAcute => Some(Sound::U),
+            Greek::OmegaAcute => Some(Sound::Ox),
+            _ => None,
+        }
     }
 
     fn can_be_softened(self) -> bool {
-        use Greek::*;
-        matches!(self, Rho | Nu | Zeta | Sigma | Delta | Tau | Lambda)
+        self.softened_sound().is_some()
+    }
+
+    fn softened_sound(self) -> Option<Sound> {
+        match self {
+            Greek::Rho => Some(Sound::Rx),
+            Greek::Nu => Some(Sound::Nx),
+            Greek::Zeta => Some(Sound::Zx),
+            Greek::Sigma => Some(Sound::Sx),
+            Greek::Delta => Some(Sound::Dx),
+            Greek::Tau => Some(Sound::Tx),
+            Greek::Lambda => Some(Sound::L),
+            _ => None,
+        }
     }
 }
 
@@ -269,6 +758,10 @@ fn to_char(greek: Greek) -> char {
         Greek::Upsilon => 'υ',
         Greek::Phi => 'φ',
         Greek::Chi => 'χ',
+        Greek::Heta => 'ͱ',
+        Greek::Digamma => 'ϝ',
+        Greek::Koppa => 'ϙ',
+        Greek::Kai => 'ϗ',
         Greek::Psi => 'ψ',
         Greek::Omega => 'ω',
         Greek::AlphaAcute => 'ά',
@@ -276,12 +769,215 @@ fn to_char(greek: Greek) -> char {
         Greek::IotaAcute => 'ί',
         Greek::OmicronAcute => 'ό',
         Greek::UpsilonAcute => 'ύ',
+        Greek::UpsilonDiaeresis => 'ϋ',
         Greek::OmegaAcute => 'ώ',
         Greek::Acute => '\'',
+        Greek::Caron => '\u{30c}',
         Greek::Break => '\\',
     }
 }
 
+/// Vowels with tonos: the letter without it, with tonos, and the same letter with oxia
+/// from the Greek Extended block.
+const TONOS: [(char, char, char); 14] = [
+    ('α', 'ά', '\u{1f71}'),
+    ('ε', 'έ', '\u{1f73}'),
+    ('η', 'ή', '\u{1f75}'),
+    ('ι', 'ί', '\u{1f77}'),
+    ('ο', 'ό', '\u{1f79}'),
+    ('υ', 'ύ', '\u{1f7b}'),
+    ('ω', 'ώ', '\u{1f7d}'),
+    ('Α', 'Ά', '\u{1fbb}'),
+    ('Ε', 'Έ', '\u{1fc9}'),
+    ('Η', 'Ή', '\u{1fcb}'),
+    ('Ι', 'Ί', '\u{1fdb}'),
+    ('Ο', 'Ό', '\u{1ff9}'),
+    ('Υ', 'Ύ', '\u{1feb}'),
+    ('Ω', 'Ώ', '\u{1ffb}'),
+];
+
+/// Letters with a diaeresis and the letters without it, which read the diaeresis as a break
+/// unless it makes the letter `ϋ` of [`Labials::Beta`].
+const DIAERESIS: [(char, char); 4] = [('ι', 'ϊ'), ('υ', 'ϋ'), ('Ι', 'Ϊ'), ('Υ', 'Ϋ')];
+
+const COMBINING_DIAERESIS: char = '\u{308}';
+
+/// `ί` and `ύ` with a diaeresis as well, precomposed with tonos and with oxia. Read as the
+/// letter with tonos and a break, as there is no such letter.
+const DIAERESIS_TONOS: [(char, char, char); 2] =
+    [('ί', '\u{390}', '\u{1fd3}'), ('ύ', '\u{3b0}', '\u{1fe3}')];
+
+/// Combining acute and the combining tone mark it stands for.
+const COMBINING_ACUTE: [char; 2] = ['\u{301}', '\u{341}'];
+
+/// How accented letters are written in the output of [`normalize`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Normalization {
+    /// One code point per letter: `ά` as U+03AC.
+    #[default]
+    Nfc,
+    /// The letter followed by a combining acute: `ά` as U+03B1 U+0301.
+    Nfd,
+}
+
+/// Rewrites every Greek vowel with tonos (precomposed, decomposed or with oxia) and `ι` and `υ`
+/// with a diaeresis in the given form. Everything else is left as it is.
+pub fn normalize(text: &str, form: Normalization) -> String {
+    let chars = text.chars().collect::<Vec<char>>();
+    let mut result = String::with_capacity(text.len());
+    let mut i = 0;
+    while i < chars.len() {
+        let (c, consumed) = read_tonos(&chars[i..]);
+        i += consumed;
+        let (c, consumed) = read_diaeresis(c, &chars[i..]);
+        i += consumed;
+        match TONOS.iter().find(|&&(_, tonos, _)| tonos == c) {
+            Some(&(base, _, _)) if form == Normalization::Nfd => {
+                result.push(base);
+                result.push(COMBINING_ACUTE[0]);
+            }
+            _ => match DIAERESIS.iter().find(|&&(_, diaeresis)| diaeresis == c) {
+                Some(&(base, _)) if form == Normalization::Nfd => {
+                    result.push(base);
+                    result.push(COMBINING_DIAERESIS);
+                }
+                _ => result.push(c),
+            },
+        }
+    }
+    result
+}
+
+/// The first character of the input with its tonos precomposed, and how many chars it took.
+fn read_tonos(input: &[char]) -> (char, usize) {
+    for &(base, tonos, oxia) in &TONOS {
+        if input[0] == oxia {
+            return (tonos, 1);
+        }
+        if input[0] == base && input.get(1).is_some_and(|c| COMBINING_ACUTE.contains(c)) {
+            return (tonos, 2);
+        }
+    }
+    (input[0], 1)
+}
+
+/// `c` with a diaeresis from the start of `input` precomposed, and how many chars of `input`
+/// it took.
+fn read_diaeresis(c: char, input: &[char]) -> (char, usize) {
+    match DIAERESIS.iter().find(|&&(base, _)| base == c) {
+        Some(&(_, diaeresis)) if input.first() == Some(&COMBINING_DIAERESIS) => (diaeresis, 1),
+        _ => (c, 0),
+    }
+}
+
+/// A letter with both tonos and a diaeresis at the start of the input, in any form, as the
+/// letter with tonos, and how many chars it took: `ΐ` is `ί` and a break.
+fn read_diaeresis_tonos(input: &[char]) -> Option<(char, usize)> {
+    if let Some(&(tonos, ..)) = DIAERESIS_TONOS
+        .iter()
+        .find(|&&(_, precomposed, oxia)| input[0] == precomposed || input[0] == oxia)
+    {
+        return Some((tonos, 1));
+    }
+    let (with_diaeresis, diaeresis) = read_diaeresis(input[0], &input[1..]);
+    let &(base, _) = DIAERESIS
+        .iter()
+        .find(|&&(_, with)| with == with_diaeresis)?;
+    let acute = input.get(1 + diaeresis)?;
+    let &(_, tonos, _) = TONOS.iter().find(|&&(b, ..)| b == base)?;
+    COMBINING_ACUTE
+        .contains(acute)
+        .then_some((tonos, 2 + diaeresis))
+}
+
+/// Put before a char that is not to be read as a letter, see [`escape`].
+const ESCAPE: &str = "\\\\";
+
+/// Whether a char would be read as (part of) a letter or as a quotation mark.
+fn is_read(c: char) -> bool {
+    let c = fold_case(c);
+    char_to_greek(c).is_some()
+        || TONOS
+            .iter()
+            .any(|&(_, tonos, oxia)| c == tonos || c == oxia)
+        || DIAERESIS.iter().any(|&(_, diaeresis)| c == diaeresis)
+        || DIAERESIS_TONOS
+            .iter()
+            .any(|&(_, precomposed, oxia)| c == precomposed || c == oxia)
+        || COMBINING_ACUTE.contains(&c)
+        || matches!(c, 'ϐ' | 'ϑ' | 'ϕ' | '‘')
+}
+
+/// Puts [`ESCAPE`] before every char of text between words that would be read as a letter,
+/// a mark or a quotation mark: `don't` as `don\\'t`.
+fn escape(arbitrary: &str) -> Cow<'_, str> {
+    if !arbitrary.chars().any(is_read) {
+        return Cow::Borrowed(arbitrary);
+    }
+    let mut escaped = String::with_capacity(arbitrary.len() * 2);
+    for c in arbitrary.chars() {
+        if is_read(c) {
+            escaped.push_str(ESCAPE);
+        }
+        escaped.push(c);
+    }
+    escaped.into()
+}
+
+/// The input in the one form [`char_to_greek`] reads, along with the position
+/// in the input of every char of it (and of the end) and whether it was escaped.
+///
+/// Accepts tonos in any form and the symbol variants `ϐ`, `ϑ` and `ϕ`. A letter with a
+/// diaeresis is read the same whether it is one char or two: as the letter and a break,
+/// or as `ϋ` if the `labials` read it. With tonos as well it is always the letter with tonos
+/// and a break, see [`read_diaeresis_tonos`]. A char after [`ESCAPE`] is kept as it is.
+fn prepare_input(input: &str, labials: Labials) -> (Vec<char>, Vec<usize>, Vec<bool>) {
+    let chars = input.chars().collect::<Vec<char>>();
+    let mut prepared = Vec::with_capacity(chars.len());
+    let mut positions = Vec::with_capacity(chars.len() + 1);
+    let mut escaped = Vec::with_capacity(chars.len());
+    let mut i = 0;
+    while i < chars.len() {
+        if chars[i..].starts_with(&['\\', '\\']) && i + 2 < chars.len() {
+            prepared.push(chars[i + 2]);
+            positions.push(i);
+            escaped.push(true);
+            i += 3;
+            continue;
+        }
+        if let Some((tonos, consumed)) = read_diaeresis_tonos(&chars[i..]) {
+            prepared.extend([tonos, COMBINING_DIAERESIS]);
+            positions.extend([i, i + consumed - 1]);
+            escaped.extend([false, false]);
+            i += consumed;
+            continue;
+        }
+        let (c, consumed) = read_tonos(&chars[i..]);
+        let (c, diaeresis) = read_diaeresis(c, &chars[i + consumed..]);
+        let letter = matches!(c, 'ϋ' | 'Ϋ') && labials == Labials::Beta;
+        match DIAERESIS.iter().find(|&&(_, with)| with == c) {
+            Some(&(base, _)) if !letter => {
+                prepared.extend([base, COMBINING_DIAERESIS]);
+                positions.extend([i, if diaeresis > 0 { i + consumed } else { i }]);
+                escaped.extend([false, false]);
+            }
+            _ => {
+                prepared.push(match c {
+                    'ϐ' => 'β',
+                    'ϑ' => 'θ',
+                    'ϕ' => 'φ',
+                    c => c,
+                });
+                positions.push(i);
+                escaped.push(false);
+            }
+        }
+        i += consumed + diaeresis;
+    }
+    positions.push(i);
+    (prepared, positions, escaped)
+}
+
 fn char_to_greek(c: char) -> Option<Greek> {
     match c {
         'α' => Some(Greek::Alpha),
@@ -302,11 +998,15 @@ fn char_to_greek(c: char) -> Option<Greek> {
         'ο' => Some(Greek::Omicron),
         'π' => Some(Greek::Pi),
         'ρ' => Some(Greek::Rho),
-        'σ' => Some(Greek::Sigma),
+        'σ' | 'ς' => Some(Greek::Sigma),
         'τ' => Some(Greek::Tau),
         'υ' => Some(Greek::Upsilon),
         'φ' => Some(Greek::Phi),
         'χ' => Some(Greek::Chi),
+        'ͱ' => Some(Greek::Heta),
+        'ϝ' => Some(Greek::Digamma),
+        'ϙ' => Some(Greek::Koppa),
+        'ϗ' => Some(Greek::Kai),
         'ψ' => Some(Greek::Psi),
         'ω' => Some(Greek::Omega),
         'ά' => Some(Greek::AlphaAcute),
@@ -314,49 +1014,129 @@ fn char_to_greek(c: char) -> Option<Greek> {
         'ί' => Some(Greek::IotaAcute),
         'ό' => Some(Greek::OmicronAcute),
         'ύ' => Some(Greek::UpsilonAcute),
+        'ϋ' => Some(Greek::UpsilonDiaeresis),
         'ώ' => Some(Greek::OmegaAcute),
-        '\'' => Some(Greek::Acute),
-        '\\' => Some(Greek::Break),
+        '\'' | '’' | 'ʼ' => Some(Greek::Acute),
+        '\u{30c}' => Some(Greek::Caron),
+        // every break mark of every variant
+        '\\' | '\u{308}' | '\u{200c}' => Some(Greek::Break),
         _ => None,
     }
 }
 
-fn naive_greek_to_sound(g: Greek) -> &'static [Sound] {
-    match g {
-        Greek::Alpha => &[Sound::A],
-        Greek::Beta => &[Sound::W],
-        Greek::Gamma => &[Sound::G],
-        Greek::Delta => &[Sound::D],
-        Greek::Epsilon => &[Sound::E],
-        Greek::Zeta => &[Sound::Z],
-        Greek::Eta => &[Sound::Ex],
-        Greek::Theta => &[Sound::Ch],
-        Greek::Iota => &[Sound::Y],
-        Greek::Kappa => &[Sound::K],
-        Greek::Lambda => &[Sound::Lx],
-        Greek::Mu => &[Sound::M],
-        Greek::Nu => &[Sound::N],
-        Greek::Xi => &[Sound::Zh],
-        Greek::Omicron => &[Sound::O],
-        Greek::Pi => &[Sound::P],
-        Greek::Rho => &[Sound::R],
-        Greek::Sigma => &[Sound::S],
-        Greek::Tau => &[Sound::T],
-        Greek::Upsilon => &[Sound::U],
-        Greek::Phi => &[Sound::F],
-        Greek::Chi => &[Sound::H],
-        Greek::Psi => &[Sound::Sh],
-        Greek::Omega => &[Sound::Ox],
-        Greek::AlphaAcute => &[Sound::J, Sound::A],
-        Greek::EpsilonAcute => &[Sound::J, Sound::E],
-        Greek::IotaAcute => &[Sound::I],
-        Greek::OmicronAcute => &[Sound::J, Sound::O],
-        Greek::UpsilonAcute => &[Sound::J, Sound::U],
-        Greek::OmegaAcute => &[Sound::J, Sound::Ox],
-        Greek::Acute => &[Sound::J],
-        Greek::Break => &[],
-        Greek::EtaAcute => &[Sound::J, Sound::Ex],
-    }
+/// Every letter and group of letters of [`Scheme::V1`], with the sounds it is read as.
+/// Consonants softened by the letter after them are added to these, see [`softened`], unless
+/// listed here.
+const V1_LETTERS: &[(&[Greek], &[Sound])] = {
+    use Greek::*;
+    use Sound::*;
+    &[
+        (&[Alpha], &[A]),
+        (&[Gamma], &[G]),
+        (&[Delta], &[D]),
+        (&[Epsilon], &[E]),
+        (&[Zeta], &[Z]),
+        (&[Eta], &[Ex]),
+        (&[Iota], &[Y]),
+        (&[Kappa], &[K]),
+        (&[Lambda], &[Lx]),
+        (&[Mu], &[M]),
+        (&[Nu], &[N]),
+        (&[Omicron], &[O]),
+        (&[Pi], &[P]),
+        (&[Rho], &[R]),
+        (&[Sigma], &[S]),
+        (&[Tau], &[T]),
+        (&[Phi], &[F]),
+        (&[Chi], &[H]),
+        (&[Heta], &[Hx]),
+        (&[Digamma], &[V]),
+        (&[Koppa], &[Q]),
+        (&[Kai], &[X]),
+        (&[Omega], &[Ox]),
+        (&[AlphaAcute], &[J, A]),
+        (&[EpsilonAcute], &[J, E]),
+        (&[EtaAcute], &[J, Ex]),
+        (&[IotaAcute], &[I]),
+        (&[OmicronAcute], &[J, O]),
+        (&[OmegaAcute], &[J, Ox]),
+        (&[Acute], &[J]),
+        (&[Break], &[]),
+        (&[Tau, Sigma], &[C]),
+        (&[Delta, Zeta], &[Dz]),
+        // rzy, in place of the softened ri
+        (&[Rho, IotaAcute], &[Rx, Y]),
+    ]
+};
+
+/// The letters of [`Sibilants::Letters`] in [`Scheme::V1`].
+const V1_SIBILANT_LETTERS: &[(&[Greek], &[Sound])] = {
+    use Greek::*;
+    use Sound::*;
+    &[
+        (&[Theta], &[Ch]),
+        (&[Psi], &[Sh]),
+        (&[Xi], &[Zh]),
+        (&[Delta, Xi], &[Dh]),
+    ]
+};
+
+/// The letters of [`Sibilants::Caron`] in [`Scheme::V1`].
+const V1_SIBILANT_CARONS: &[(&[Greek], &[Sound])] = {
+    use Greek::*;
+    use Sound::*;
+    &[
+        (&[Tau, Sigma, Caron], &[Ch]),
+        (&[Sigma, Caron], &[Sh]),
+        (&[Zeta, Caron], &[Zh]),
+        (&[Delta, Zeta, Caron], &[Dh]),
+    ]
+};
+
+/// The letters of [`Labials::MuPi`] in [`Scheme::V1`].
+const V1_LABIALS_MU_PI: &[(&[Greek], &[Sound])] = {
+    use Greek::*;
+    use Sound::*;
+    &[
+        (&[Beta], &[W]),
+        (&[Mu, Pi], &[B]),
+        (&[Upsilon], &[U]),
+        (&[UpsilonAcute], &[J, U]),
+        (&[Omicron, Upsilon], &[Ou]),
+        (&[OmicronAcute, Upsilon], &[J, Ou]),
+    ]
+};
+
+/// The letters of [`Labials::Beta`] in [`Scheme::V1`].
+const V1_LABIALS_BETA: &[(&[Greek], &[Sound])] = {
+    use Greek::*;
+    use Sound::*;
+    &[
+        (&[Beta], &[B]),
+        (&[Upsilon], &[W]),
+        (&[UpsilonDiaeresis], &[U]),
+        // w is never softened, so ύ is still ju
+        (&[UpsilonAcute], &[J, U]),
+        (&[Omicron, UpsilonDiaeresis], &[Ou]),
+        (&[OmicronAcute, UpsilonDiaeresis], &[J, Ou]),
+    ]
+};
+
+/// A consonant followed by a letter that softens it: `σά` is `[Sx, A]`, `σ'` is `[Sx]`.
+/// The vowels are taken from `letters`, where they are read as `j` + vowel: `ά` is `[J, A]`.
+fn softened<'a>(
+    consonant: Greek,
+    soft: Sound,
+    letters: &'a [(&[Greek], &[Sound])],
+) -> impl Iterator<Item = (Vec<Greek>, Vec<Sound>)> + 'a {
+    let vowels = letters
+        .iter()
+        .filter(|(letters, _)| letters[0].softening_vowel().is_some())
+        .map(move |&(letters, sounds)| {
+            let vowel = sounds.strip_prefix(&[Sound::J]).unwrap_or(sounds);
+            ([&[consonant], letters].concat(), [&[soft], vowel].concat())
+        });
+    std::iter::once((vec![consonant, Greek::Acute], vec![soft])).chain(vowels)
 }
 
 struct ParseGrResult {
@@ -376,177 +1156,268 @@ struct ParseOfResult {
 //     }
 // }
 
-fn greek_vec_to_sound(input_initial: &[Greek]) -> ParseOfResult {
-    let mut result = vec![];
-    use Greek::*;
-    use Sound::*;
-    let mut i = 0;
-    while i < input_initial.len() {
-        let input = &input_initial[i..];
-        let c0 = input[0];
-        if c0 == Break {
-            i += 1;
-            continue;
-        }
+/// Reads a word, and tells for every sound which letter it was read from.
+fn greek_vec_to_sound_with_origins(
+    tables: &Tables,
+    input_initial: &[Greek],
+) -> (ParseOfResult, Vec<usize>) {
+    let (mut result, mut origins) = (vec![], vec![]);
+    let consumed = tables.read(input_initial, &mut result, &mut origins);
+    (ParseOfResult { result, consumed }, origins)
+}
 
-        let mut softened_count = 0;
-        let mut was_rho = false;
-        while softened_count < input.len() {
-            let isc = input[softened_count];
-            if !isc.can_be_softened() {
-                break;
-            }
-            if isc != Rho && was_rho {
-                softened_count = 0; // no softening for you
-                break;
-            }
-            if isc == Rho {
-                was_rho = true;
-            }
-            if softened_count > 0 && was_rho {
-                break;
-            }
-            softened_count += 1;
-        }
+impl Tables {
+    /// Reads letters into sounds appended to `result`, and the letter every sound was read from
+    /// into `origins`. Returns how many letters were read.
+    fn read(&self, input: &[Greek], result: &mut Vec<Sound>, origins: &mut Vec<usize>) -> usize {
+        self.read_each(input, |at, consumed, sounds| {
+            result.extend_from_slice(sounds);
+            origins.extend(crate::step_origins(at, consumed, sounds.len()));
+            true
+        })
+    }
 
-        if softened_count > 0
-            && input.len() > softened_count
-            && input[softened_count].is_softening()
-        {
-            for inpj in input.iter().take(softened_count) {
-                let q = match inpj {
-                    Rho => Rx,
-                    Nu => Nx,
-                    Zeta => Zx,
-                    Sigma => Sx,
-                    Delta => Dx,
-                    Tau => Tx,
-                    Lambda => L,
-                    _ => panic!("nie zmiękczalny :("),
-                };
-                result.push(q);
-                i += 1;
-            }
+    /// Reads letters into sounds, without telling where each of them was read from.
+    fn read_sounds(&self, input: &[Greek]) -> ParseOfResult {
+        let mut result = Vec::with_capacity(input.len());
+        let consumed = self.read_each(input, |_, _, sounds| {
+            result.extend_from_slice(sounds);
+            true
+        });
+        ParseOfResult { result, consumed }
+    }
 
-            if input[softened_count] == Acute {
-                // not in result
-                i += 1
-            } else {
-                let c = match input[softened_count] {
-                    Greek::AlphaAcute => A,
-                    Greek::EpsilonAcute => E,
-                    Greek::EtaAcute => Ex,
-                    Greek::IotaAcute => {
-                        if c0 == Rho {
-                            Y
-                        } else {
-                            I
-                        }
-                    } // na pewno
-                    Greek::OmicronAcute => O,
-                    Greek::UpsilonAcute => U,
-                    Greek::OmegaAcute => Ox,
-                    _ => panic!("no soft :("),
-                };
-                if c == O && input.get(softened_count + 1) == Some(&Upsilon) {
-                    result.push(Ou);
-                    i += 2;
-                } else {
-                    result.push(c);
-                    i += 1;
+    /// Whether the letters are read as exactly the given sounds.
+    fn reads_as(&self, letters: &[Greek], sounds: &[Sound]) -> bool {
+        let mut read = 0;
+        let consumed = self.read_each(letters, |_, _, step| {
+            let fits = sounds[read..].starts_with(step);
+            read += step.len();
+            fits
+        });
+        consumed == letters.len() && read == sounds.len()
+    }
+
+    /// Reads letters, telling `each` where every letter or group of letters read together
+    /// starts, how many letters it takes and its sounds, for as long as `each` returns `true`.
+    /// Returns how many letters were read.
+    fn read_each(
+        &self,
+        input: &[Greek],
+        mut each: impl FnMut(usize, usize, &[Sound]) -> bool,
+    ) -> usize {
+        let mut i = 0;
+        while i < input.len() {
+            let rest = &input[i..];
+            let soft;
+            let (sounds, consumed) = match self.softened_in_cluster(rest) {
+                Some(sound) => {
+                    soft = [sound];
+                    (&soft[..], 1)
                 }
+                None => match self.graphemes.longest_match(rest) {
+                    Some(found) => found,
+                    None => break,
+                },
+            };
+            if !each(i, consumed, sounds) {
+                break;
             }
-
-            continue;
+            i += consumed;
         }
+        i
+    }
 
-        ///// DWUZNAKI
-        if input.len() > 1 {
-            let c1 = input[1];
-            if c0 == Tau && c1 == Sigma {
-                i += 2;
-                result.push(C);
-                continue;
-            }
-            if c0 == Delta && c1 == Zeta {
-                i += 2;
-                result.push(Dz);
-                continue;
-            }
-            if c0 == Delta && c1 == Xi {
-                i += 2;
-                result.push(Dh);
-                continue;
-            }
-            if c0 == Mu && c1 == Pi {
-                i += 2;
-                result.push(B);
-                continue;
-            }
-            if c0 == Omicron && c1 == Upsilon {
-                i += 2;
-                result.push(Ou);
-                continue;
-            }
-            if c0 == OmicronAcute && c1 == Upsilon {
-                i += 2;
-                result.push(J);
-                result.push(Ou);
-                continue;
+    /// The first consonant of a cluster softened as a whole by the letter after it: `σν` in `σνέκ`.
+    /// The last consonant is read together with that letter. `ρ` is softened only on its own.
+    ///
+    /// With [`Variant::soft_labials`], labials pass the softening of a vowel on without being
+    /// softened themselves: `σ` in `σμέ`.
+    fn softened_in_cluster(&self, input: &[Greek]) -> Option<Sound> {
+        let soft = |g: &Greek| *g != Greek::Rho && g.can_be_softened();
+        let cluster = input.iter().take_while(|g| soft(g)).count();
+        let mut end = cluster;
+        if self.variant.soft_labials && cluster > 0 {
+            while let Some((&[sound], len)) = self.graphemes.longest_match(&input[end..])
+                && matches!(sound, Sound::M | Sound::P | Sound::B | Sound::W | Sound::F)
+            {
+                end += len;
             }
         }
-        // EODWUZNAKI
-
-        let snd = naive_greek_to_sound(c0);
-        result.extend(snd);
-        i += 1
-    }
-
-    ParseOfResult {
-        result,
-        consumed: i,
+        let softened = match input.get(end) {
+            Some(g) if end > cluster => g.softening_vowel().is_some(),
+            Some(g) => cluster >= 2 && g.is_softening(),
+            None => false,
+        };
+        if softened {
+            input[0].softened_sound()
+        } else {
+            None
+        }
     }
 }
 
 #[derive(PartialEq, Eq, Debug)]
-pub enum TextRepr {
+pub(crate) enum TextRepr {
     Arbitrary(String),
     Word(Vec<Greek>, CapitalisationMode),
 }
 
 #[derive(PartialEq, Eq, Debug)]
-pub struct GreekText {
+pub(crate) struct GreekText {
     pub parts: Vec<TextRepr>,
 }
 
+/// Parses text written in the Greek script into the intermediate representation.
+///
+/// Never fails: letters that cannot be read are kept in place as
+/// [`TextRepr::Arbitrary`](super::official::TextRepr::Arbitrary).
 pub fn utf8_greek_to_text(input: &str) -> super::official::Text {
-    let greek_text = utf8_to_greek(input);
-    let prepared = greek_text
-        .parts
-        .iter()
-        .map(|part| match part {
+    utf8_greek_to_text_recovering(&DEFAULT, input).0
+}
+
+/// Like [`utf8_greek_to_text`], but fails on the first letter that cannot be read.
+pub fn try_utf8_greek_to_text(input: &str) -> Result<super::official::Text, Error> {
+    match utf8_greek_to_text_recovering(&DEFAULT, input) {
+        (text, None) => Ok(text),
+        (_, Some(error)) => Err(error),
+    }
+}
+
+fn utf8_greek_to_text_recovering(
+    tables: &Tables,
+    input: &str,
+) -> (super::official::Text, Option<Error>) {
+    let (chars, positions, escaped) = prepare_input(input, tables.variant.labials);
+    let (greek_text, mut error) = utf8_to_greek(tables, &chars, &escaped);
+    let mut prepared = vec![];
+    let mut offset = 0;
+    for part in greek_text.parts {
+        match part {
             TextRepr::Arbitrary(arbitrary) => {
-                super::official::TextRepr::Arbitrary(arbitrary.clone())
+                offset += arbitrary.chars().count();
+                prepared.push(super::official::TextRepr::Arbitrary(arbitrary));
             }
             TextRepr::Word(word, orig) => {
-                let parse_result = greek_vec_to_sound(word);
-                // TODO ignore count - sanity check
+                let (parse_result, capitalisation_mode) = match orig {
+                    // from the case of every letter to the case of every sound
+                    CapitalisationMode::Mixed(letters) => {
+                        let (parse_result, origins) =
+                            greek_vec_to_sound_with_origins(tables, &word);
+                        let case = origins.iter().map(|&origin| letters[origin]).collect();
+                        (parse_result, CapitalisationMode::Mixed(case))
+                    }
+                    orig => (tables.read_sounds(&word), orig),
+                };
+                prepared.push(super::official::TextRepr::Word(
+                    parse_result.result,
+                    capitalisation_mode,
+                ));
                 if parse_result.consumed != word.len() {
-                    panic!("Not all parsed")
+                    // keep whatever was not understood as it is
+                    let rest = &word[parse_result.consumed..];
+                    error.get_or_insert(Error::UnknownLetter {
+                        offset: offset + parse_result.consumed,
+                        ch: to_char(rest[0]),
+                    });
+                    prepared.push(super::official::TextRepr::Arbitrary(
+                        rest.iter().map(|&g| to_char(g)).collect(),
+                    ));
                 }
-                super::official::TextRepr::Word(parse_result.result, *orig)
+                offset += word.len();
             }
-        })
-        .collect::<Vec<super::official::TextRepr>>();
+        }
+    }
+
+    // back from the prepared input to the one given
+    if let Some(Error::UnknownLetter { offset, .. }) = &mut error {
+        *offset = positions[*offset];
+    }
+
+    (super::official::Text { parts: prepared }, error)
+}
 
-    super::official::Text { parts: prepared }
+/// Which apostrophes of the (lowercase) input are quotation marks rather than [`Greek::Acute`]:
+/// those not next to any Greek letter (`don't`), those starting a word before a letter
+/// the `tables` do not read as a vowel, and those around a phrase on one line
+/// (`'άλα μα κοτα'`).
+///
+/// An apostrophe that only ends a word is a quotation mark only if one before it on the same
+/// line opened the quote, otherwise it softens: `ραδοστ'`. An apostrophe before `ί` or `ι`
+/// never opens a quote, it is `j`: `'ίμ`. Neither does one after a break, which is how
+/// a word starting with `j` before a consonant is written: `\'ρί`. Escaped chars are neither
+/// letters nor quotation marks.
+fn quotation_marks(tables: &Tables, input: &[char], escaped: &[bool]) -> Vec<bool> {
+    let greek = |k: Option<usize>| {
+        k.filter(|&k| k < input.len() && !escaped[k])
+            .and_then(|k| char_to_greek(input[k]))
+    };
+    let letter = |k: Option<usize>| greek(k).filter(|&g| g != Greek::Acute && g != Greek::Break);
+
+    let mut quotes = vec![false; input.len()];
+    let mut open = vec![];
+    for (k, &c) in input.iter().enumerate() {
+        if c == '\n' {
+            open.clear();
+            continue;
+        }
+        if escaped[k] {
+            continue;
+        }
+        if c == '‘' {
+            open.push(None);
+            continue;
+        }
+        if char_to_greek(c) != Some(Greek::Acute) || greek(k.checked_sub(1)) == Some(Greek::Break) {
+            continue;
+        }
+        let before = letter(k.checked_sub(1));
+        let after = letter(Some(k + 1));
+        match (before, after) {
+            (None, None) => quotes[k] = true,
+            (None, Some(Greek::Iota | Greek::IotaAcute)) => {}
+            (None, Some(after)) => {
+                quotes[k] = !tables.vowels.contains(&after);
+                open.push(Some(k));
+            }
+            (Some(_), None) => {
+                if let Some(opening) = open.pop() {
+                    quotes[k] = true;
+                    if let Some(opening) = opening {
+                        quotes[opening] = true;
+                    }
+                }
+            }
+            (Some(_), Some(_)) => {}
+        }
+    }
+    quotes
 }
 
-fn utf8_to_greek(input: &str) -> GreekText {
-    let case_preserving_input = input.chars().collect::<Vec<char>>();
-    let chars_input: Vec<char> = input.to_lowercase().chars().collect();
+fn utf8_to_greek(
+    tables: &Tables,
+    case_preserving_input: &[char],
+    escaped: &[bool],
+) -> (GreekText, Option<Error>) {
+    let chars_input: Vec<char> = case_preserving_input
+        .iter()
+        .map(|&c| fold_case(c))
+        .collect();
+    // quotation marks and escaped chars are not read
+    let mut quotes = quotation_marks(tables, &chars_input, escaped);
+    for (quote, &escaped) in quotes.iter_mut().zip(escaped) {
+        *quote |= escaped;
+    }
+    let letter = |k: usize| {
+        if quotes[k] {
+            None
+        } else {
+            char_to_greek(chars_input[k])
+        }
+    };
 
     let mut parts = vec![];
+    let mut error = None;
     let mut i = 0;
 
     while i < chars_input.len() {
@@ -555,49 +1426,75 @@ fn utf8_to_greek(input: &str) -> GreekText {
             break;
         }
 
-        let cr = consume_utf8_word(chars);
+        let mut cr = consume_utf8_word(chars, &quotes[i..]);
         if cr.consumed > 0 {
-            parts.push(TextRepr::Word(
-                cr.result,
-                super::CapitalisationMode::detect(&case_preserving_input[i..i + cr.consumed]),
-            ));
+            // the case of the whole word, as the pieces a break splits it into may have
+            // no capital letter of their own: Κ'\Α for KJA. Every Greek letter is
+            // a single character.
+            let word = &case_preserving_input[i..i + cr.consumed];
+            let mut case = CapitalisationMode::detect_per_sound(word, 0..cr.consumed);
+            let mut len = cr.consumed;
+            // a break ending a word marks a word of one capital letter as written in capitals
+            if len > 1 && cr.result[len - 1] == Greek::Break && case.read_capitals_mark(word) {
+                len -= 1;
+            }
+            // a break splits the word in two, the break itself starts the second one
+            let mut start = 0;
+            for end in 1..=len {
+                if end == len || cr.result[end] == Greek::Break {
+                    let letters = if start == 0 && end == cr.consumed {
+                        std::mem::take(&mut cr.result)
+                    } else {
+                        cr.result[start..end].to_vec()
+                    };
+                    parts.push(TextRepr::Word(letters, case.part(start..end)));
+                    start = end;
+                }
+            }
             i += cr.consumed;
             continue;
         }
 
         let mut j = 0;
-        while j < chars.len() && char_to_greek(chars[j]).is_none() {
+        while j < chars.len() && letter(i + j).is_none() {
+            if chars[j].is_alphabetic() && !escaped[i + j] && error.is_none() {
+                error = Some(Error::UnknownLetter {
+                    offset: i + j,
+                    ch: case_preserving_input[i + j],
+                });
+            }
             j += 1;
         }
 
         if j > 0 {
-            parts.push(TextRepr::Arbitrary(chars[..j].iter().collect()));
+            parts.push(TextRepr::Arbitrary(
+                case_preserving_input[i..i + j].iter().collect(),
+            ));
             i += j;
             continue;
         }
 
-        print!("Failed to parse at index {}: '{}'", i, chars[0]);
-        break;
+        // nothing parsed, keep the character as it is
+        error.get_or_insert(Error::UnknownLetter {
+            offset: i,
+            ch: case_preserving_input[i],
+        });
+        parts.push(TextRepr::Arbitrary(case_preserving_input[i].to_string()));
+        i += 1;
     }
 
-    GreekText { parts }
+    (GreekText { parts }, error)
 }
 
-fn consume_utf8_word(input: &[char]) -> ParseGrResult {
-    let mut i = 0;
-    let mut result = Vec::new();
-    while i < input.len() {
-        match char_to_greek(input[i]) {
-            Some(g) => {
-                result.push(g);
-                i += 1;
-            }
-            None => break,
-        }
-    }
+fn consume_utf8_word(input: &[char], quotes: &[bool]) -> ParseGrResult {
+    let result = input
+        .iter()
+        .zip(quotes)
+        .map_while(|(&c, &quote)| char_to_greek(c).filter(|_| !quote))
+        .collect::<Vec<Greek>>();
     ParseGrResult {
+        consumed: result.len(),
         result,
-        consumed: i,
     }
 }
 
@@ -605,6 +1502,10 @@ fn consume_utf8_word(input: &[char]) -> ParseGrResult {
 mod tests {
     use super::*;
 
+    fn greek_vec_to_sound(input: &[Greek]) -> ParseOfResult {
+        greek_vec_to_sound_with_origins(&DEFAULT, input).0
+    }
+
     #[test]
     fn gr_vec_to_sound_vec() {
         use Greek::*;
@@ -702,9 +1603,22 @@ mod tests {
         );
     }
 
+    #[test]
+    fn softened_cluster() {
+        use Greek::*;
+        use Sound::*;
+        let (r, origins) =
+            greek_vec_to_sound_with_origins(&DEFAULT, &[Sigma, Nu, Acute, OmegaAcute]);
+        assert_eq!(r.result, [Sx, Nx, J, Ox]);
+        // every consonant of the cluster is read from its own letter
+        assert_eq!(origins, [0, 1, 3, 3]);
+        assert_eq!(crate::greek_to_official("σΝ'ώ"), "śŃją");
+    }
+
     #[test]
     fn utf8_do_gr() {
-        let res = utf8_to_greek("ποζδραβάμ τέπλύτκο! :)");
+        let input = "ποζδραβάμ τέπλύτκο! :)".chars().collect::<Vec<char>>();
+        let (res, _) = utf8_to_greek(&DEFAULT, &input, &vec![false; input.len()]);
         assert_eq!(res.parts.len(), 4);
         assert_eq!(
             res.parts[0],
@@ -742,7 +1656,8 @@ mod tests {
         );
         assert_eq!(res.parts[3], TextRepr::Arbitrary("! :)".into()));
 
-        let res = utf8_to_greek("ραδοστ'");
+        let input = "ραδοστ'".chars().collect::<Vec<char>>();
+        let (res, _) = utf8_to_greek(&DEFAULT, &input, &vec![false; input.len()]);
         assert_eq!(res.parts.len(), 1);
         assert_eq!(
             res.parts[0],
@@ -763,25 +1678,10 @@ mod tests {
 
     #[test]
     fn test_to_greek() {
-        let input = vec![Sound::C];
-        let result = consume_greek(&input);
-        assert_eq!(result.result, vec![Greek::Tau, Greek::Sigma]);
-        assert_eq!(result.consumed, 1);
-
-        let input = vec![Sound::Tx];
-        let result = consume_greek(&input);
-        assert_eq!(result.result, vec![Greek::Tau, Greek::Acute]);
-        assert_eq!(result.consumed, 1);
-
-        let input = vec![Sound::Ox];
-        let result = consume_greek(&input);
-        assert_eq!(result.result, vec![Greek::Omega]);
-        assert_eq!(result.consumed, 1);
-
-        let input = vec![Sound::Sx, Sound::A];
-        let result = consume_greek(&input);
-        assert_eq!(result.result, vec![Greek::Sigma, Greek::AlphaAcute]);
-        assert_eq!(result.consumed, 2);
+        assert_eq!(to_greek(&[Sound::C]), "τσ");
+        assert_eq!(to_greek(&[Sound::Tx]), "τ'");
+        assert_eq!(to_greek(&[Sound::Ox]), "ω");
+        assert_eq!(to_greek(&[Sound::Sx, Sound::A]), "σά");
     }
 
     #[test]
@@ -795,33 +1695,296 @@ mod tests {
         let result = to_greek(&input);
         assert_eq!(result, "ραδοστί");
 
-        assert_eq!(to_greek(&vec![L, I, T, W, O]), "λίτβο");
-        assert_eq!(to_greek(&vec![O, J, Ch, Y, Z, N, O]), "ο'θιζνο");
-        assert_eq!(to_greek(&vec![M, O, J, A]), "μοά");
-        assert_eq!(to_greek(&vec![T, Y]), "τι");
-        assert_eq!(to_greek(&vec![J, E, S, T, E, Sx]), "έστεσ'");
-        assert_eq!(to_greek(&vec![J, A, K]), "άκ");
-        assert_eq!(to_greek(&vec![Z, D, R, O, W, J, E]), "ζδροβέ");
-
-        assert_eq!(to_greek(&vec![I, L, E]), "ίλέ");
-        assert_eq!(to_greek(&vec![Tx, Ex]), "τή");
-        assert_eq!(to_greek(&vec![T, Rx, E, B, A]), "τρέμπα");
-        assert_eq!(to_greek(&vec![C, E, Nx, I, Tx]), "τσενίτ'");
-        assert_eq!(to_greek(&vec![T, E, N]), "τεν");
-        assert_eq!(to_greek(&vec![T, Y, L, K, O]), "τιλ'κο");
-        assert_eq!(to_greek(&vec![Sx, Ex]), "σή");
-        assert_eq!(to_greek(&vec![D, O, W, J, E]), "δοβέ");
-        assert_eq!(to_greek(&vec![C, O]), "τσο");
-        assert_eq!(to_greek(&vec![Tx, Ex]), "τή");
-        assert_eq!(to_greek(&vec![S, T, R, A, Tx, I, Lx]), "στρατίλ");
-        assert_eq!(to_greek(&vec![M, J, U, D]), "μύδ");
-        assert_eq!(to_greek(&vec![M, J, Ou, D]), "μόυδ");
+        assert_eq!(to_greek(&[L, I, T, W, O]), "λίτβο");
+        assert_eq!(to_greek(&[O, J, Ch, Y, Z, N, O]), "ο'θιζνο");
+        assert_eq!(to_greek(&[M, O, J, A]), "μοά");
+        assert_eq!(to_greek(&[T, Y]), "τι");
+        assert_eq!(to_greek(&[J, E, S, T, E, Sx]), "έστεσ'");
+        assert_eq!(to_greek(&[J, A, K]), "άκ");
+        assert_eq!(to_greek(&[Z, D, R, O, W, J, E]), "ζδροβέ");
+
+        assert_eq!(to_greek(&[I, L, E]), "ίλέ");
+        assert_eq!(to_greek(&[Tx, Ex]), "τή");
+        assert_eq!(to_greek(&[T, Rx, E, B, A]), "τρέμπα");
+        assert_eq!(to_greek(&[C, E, Nx, I, Tx]), "τσενίτ'");
+        assert_eq!(to_greek(&[T, E, N]), "τεν");
+        assert_eq!(to_greek(&[T, Y, L, K, O]), "τιλ'κο");
+        assert_eq!(to_greek(&[Sx, Ex]), "σή");
+        assert_eq!(to_greek(&[D, O, W, J, E]), "δοβέ");
+        assert_eq!(to_greek(&[C, O]), "τσο");
+        assert_eq!(to_greek(&[Tx, Ex]), "τή");
+        assert_eq!(to_greek(&[S, T, R, A, Tx, I, Lx]), "στρατίλ");
+        assert_eq!(to_greek(&[M, J, U, D]), "μύδ");
+        assert_eq!(to_greek(&[M, J, Ou, D]), "μόυδ");
+
+        // a break is decided from the letters since the last one read on its own
+        assert_eq!(to_greek(&[Dx, Rx, H, Tx, J, E, Dx]), "δρ'χτ'έδ'");
+        let podzielic = [P, O, D, Zx, E, L, I, Tx];
+        assert_eq!(
+            to_greek(&podzielic.repeat(1000)),
+            "ποδ\\ζέλίτ'".repeat(1000)
+        );
+    }
+
+    #[test]
+    fn typographic() {
+        use crate::convert;
+        use crate::official::OfficialScript;
+
+        assert_eq!(
+            convert(
+                "Czy masz kwas? Tak; „nasz”.",
+                &OfficialScript,
+                &GreekTypographicScript
+            ),
+            "Θι μαψ κβας; Τακ· «ναψ»."
+        );
+        assert_eq!(
+            convert(
+                "Θι μαψ κβας; Τακ· «ναψ».",
+                &GreekTypographicScript,
+                &OfficialScript
+            ),
+            "Czy masz kwas? Tak; „nasz”."
+        );
+        assert_eq!(
+            to_greek_typographic(&[Sound::J, Sound::E, Sound::S, Sound::T, Sound::E, Sound::Sx]),
+            "έστεσ'"
+        );
+        assert_eq!(convert("κβας", &GreekScript, &OfficialScript), "kwas");
+    }
+
+    #[test]
+    fn variant_input() {
+        use crate::convert;
+        use crate::official::OfficialScript;
+
+        let decomposed = "ποζδραβα\u{301}μ τε\u{301}πλυ\u{301}τκο";
+        let oxia = "ποζδραβ\u{1f71}μ τ\u{1f73}πλ\u{1f7b}τκο";
+        for input in [decomposed, oxia] {
+            assert_eq!(
+                convert(input, &GreekScript, &OfficialScript),
+                "pozdrawiam cieplutko"
+            );
+        }
+        assert_eq!(
+            convert("ραδοστ’ ραδοστʼ", &GreekScript, &OfficialScript),
+            "radość radość"
+        );
+        assert_eq!(
+            convert("ϐόδα ϑι ϕ", &GreekScript, &OfficialScript),
+            "wioda czy f"
+        );
+        assert_eq!(
+            try_utf8_greek_to_text("ε\u{301}ε\u{301} ж"),
+            Err(Error::UnknownLetter {
+                offset: 5, ch: 'ж'
+            })
+        );
+    }
+
+    #[test]
+    fn quotes() {
+        use crate::convert;
+        use crate::official::OfficialScript;
+
+        let official = |greek| convert(greek, &GreekScript, &OfficialScript);
+        assert_eq!(official("'άδα μα κοτα'"), "'jada ma kota'");
+        assert_eq!(official("ραδοστ' 'τακ'"), "radość 'tak'");
+        assert_eq!(official("‘ραδοστ’"), "‘radost’");
+        assert_eq!(official("ραδοστ’"), "radość");
+        assert_eq!(official("don't ο'θιζνο"), "don't ojczyzno");
+        assert_eq!(official("'κοτ"), "'kot");
+        assert_eq!(official("' '"), "' '");
+        // quotes do not span lines
+        assert_eq!(official("'άλα\nραδοστ'"), "jjała\nradość");
+
+        // j starting a word, then a softened consonant ending a later one
+        for text in [
+            "Jim ma radość",
+            "jidysz i radość",
+            "jrzy",
+            "Jrzy, kraj",
+            "j",
+        ] {
+            let greek = convert(text, &OfficialScript, &GreekScript);
+            let back = convert(&greek, &GreekScript, &OfficialScript);
+            assert_eq!(back, text, "{greek}");
+        }
+        assert_eq!(convert("Jim", &OfficialScript, &GreekScript), "'Ίμ");
+        assert_eq!(convert("jrzy", &OfficialScript, &GreekScript), "\\'ρί");
+    }
+
+    #[test]
+    fn normalization() {
+        assert_eq!(
+            normalize("Ά\u{1f71}", Normalization::Nfd),
+            "Α\u{301}α\u{301}"
+        );
+        assert_eq!(normalize("Α\u{301}\u{1f71}", Normalization::Nfc), "Άά");
+        assert_eq!(normalize("κ\u{301}", Normalization::Nfc), "κ\u{301}");
+        assert_eq!(
+            normalize("Ϊυ\u{308}", Normalization::Nfd),
+            "Ι\u{308}υ\u{308}"
+        );
+        assert_eq!(normalize("Ι\u{308}υ\u{308}", Normalization::Nfc), "Ϊϋ");
+    }
+
+    #[test]
+    fn variants() {
+        use crate::convert;
+        use crate::official::OfficialScript;
+
+        let variant = |name: &str| name.parse::<Variant>().unwrap();
+        assert_eq!(variant(""), Variant::default());
+        assert_eq!(variant("zwnj+hacek").to_string(), "hacek+zwnj");
+        assert_eq!(Variant::default().to_string(), "");
+        assert!("hacek+runes".parse::<Variant>().is_err());
+        assert!("beta+diaeresis".parse::<Variant>().is_err());
+
+        let cases = [
+            ("hacek", "σ̌τσ̌εμπρέσ̌ιν, τσ̌αρνι δζ̌ίπ"),
+            ("beta", "ψθεβρέψιν, θαρνι δξίπ"),
+            ("diaeresis", "ψθεμπρέψιν, θαρνι δξίπ"),
+            ("soft-labials", "ψθεμπρέψιν, θαρνι δξίπ"),
+        ];
+        for (name, greek) in cases {
+            let script = GreekVariantScript::new(variant(name));
+            let official = "szczebrzeszyn, czarny dżip";
+            assert_eq!(convert(official, &OfficialScript, &script), greek, "{name}");
+            assert_eq!(convert(greek, &script, &OfficialScript), official, "{name}");
+        }
+    }
+
+    #[test]
+    fn variant_details() {
+        use crate::convert;
+        use crate::official::OfficialScript;
+
+        let both_ways = |name: &str, official: &str, greek: &str| {
+            let script = GreekVariantScript::new(name.parse().unwrap());
+            assert_eq!(convert(official, &OfficialScript, &script), greek);
+            assert_eq!(convert(greek, &script, &OfficialScript), official);
+        };
+        both_ways("beta", "wuj bije wiórem", "υϋ' βίέ υόϋρεμ");
+        both_ways("beta", "Układ auta", "Ϋκλαδ αϋτα");
+        // υ is w there, so j before it needs a break to not be read as a quotation mark
+        both_ways("beta", "jwipń", "\\'υίπν'");
+        both_ways("soft-labials", "śmieszne źwierzę", "σμέψνε ζβέρή");
+        both_ways("soft-labials", "śmiech smak śpj", "σμέχ σμακ σ'π'");
+        both_ways("diaeresis", "podżegać", "ποδ\u{308}ξεγατ'");
+        both_ways("diaeresis", "Jmość", "\\'Μοστ'");
+        both_ways("zwnj", "podżegać", "ποδ\u{200c}ξεγατ'");
+        both_ways(
+            "hacek+beta+zwnj",
+            "podżegaczu",
+            "ποδ\u{200c}ζ\u{30c}εγατσ\u{30c}ϋ",
+        );
+        // the default script keeps reading the other marks
+        assert_eq!(
+            convert("ποδ\u{308}ξεγατ'", &GreekScript, &OfficialScript),
+            "podżegać"
+        );
+
+        // a letter with a diaeresis reads the same in one char or two
+        let beta = GreekVariantScript::new("beta".parse().unwrap());
+        let greek = convert("Buk i wuj", &OfficialScript, &beta);
+        assert_eq!(greek, "Βϋκ ί υϋ'");
+        for form in [Normalization::Nfc, Normalization::Nfd] {
+            let greek = normalize(&greek, form);
+            assert_eq!(convert(&greek, &beta, &OfficialScript), "Buk i wuj");
+        }
+        let diaeresis = GreekVariantScript::new("diaeresis".parse().unwrap());
+        for greek in ["ξυ\u{308}σί Ι\u{308}κ", "ξϋσί Ϊκ"] {
+            assert_eq!(convert(greek, &diaeresis, &OfficialScript), "żusi Yk");
+            assert_eq!(convert(greek, &GreekScript, &OfficialScript), "żusi Yk");
+        }
+        // and so does one with tonos too, in both Unicode normal forms and as `ΐ`
+        let greek = crate::convert_lossless("ią", &OfficialScript, &diaeresis);
+        assert_eq!(greek, "ί\u{308}ω");
+        for greek in [
+            "ι\u{301}\u{308}ω",
+            "\u{390}ω",
+            "ι\u{308}\u{301}ω",
+            "ϊ\u{301}ω",
+            "\u{1fd3}ω",
+        ] {
+            assert_eq!(convert(greek, &diaeresis, &OfficialScript), "ią");
+        }
+        assert_eq!(convert("Σ\u{3b0}ν", &diaeresis, &OfficialScript), "Siun");
+    }
+
+    #[test]
+    fn schemes() {
+        assert_eq!("v1".parse(), Ok(Scheme::V1));
+        assert!("v2".parse::<Scheme>().is_err());
+        let script =
+            |variant: &str| GreekVariantScript::with_scheme(variant.parse().unwrap(), Scheme::V1);
+        assert_eq!(script("").name(), "grecki@v1");
+        assert_eq!(script("beta+hacek").name(), "grecki+hacek+beta@v1");
+        assert_eq!(
+            script("hacek").description(),
+            "Greek, hacek, v1 / Grecki, hacek, v1"
+        );
+        assert_eq!(
+            GreekVariantScript::new(Variant::default()).scheme(),
+            Scheme::default()
+        );
+    }
+
+    #[test]
+    fn scheme_v1_unchanged() {
+        // text written with v1 must read the same for good: a change to the tables of any
+        // variant needs a new scheme, not a new version of this file
+        use std::fmt::Write;
+        let mut snapshot = String::new();
+        for sibilants in [Sibilants::Letters, Sibilants::Caron] {
+            for labials in [Labials::MuPi, Labials::Beta] {
+                for breaks in [BreakMark::Backslash, BreakMark::Diaeresis, BreakMark::Zwnj] {
+                    for soft_labials in [false, true] {
+                        let variant = Variant {
+                            sibilants,
+                            labials,
+                            breaks,
+                            soft_labials,
+                        };
+                        if variant.to_string().parse() != Ok(variant) {
+                            continue;
+                        }
+                        let script = GreekVariantScript::with_scheme(variant, Scheme::V1);
+                        writeln!(snapshot, "[{}]", script.name()).unwrap();
+                        for (letters, sounds) in reading(variant, Scheme::V1) {
+                            let sounds = sounds.iter().map(|sound| format!(" {sound}"));
+                            let letters = script.tables.spell(letters);
+                            writeln!(snapshot, "{letters} ={}", sounds.collect::<String>())
+                                .unwrap();
+                        }
+                        writeln!(snapshot, "{}", script.tables.writing).unwrap();
+                    }
+                }
+            }
+        }
+        assert_eq!(snapshot, include_str!("../schemes/greek-v1.txt"));
+    }
+
+    #[cfg(feature = "json")]
+    #[test]
+    fn scheme_v1_json_unchanged() {
+        let script = GreekVariantScript::with_scheme(Variant::default(), Scheme::V1);
+        assert_eq!(
+            script.tables_json() + "\n",
+            include_str!("../schemes/greek-v1.json")
+        );
+        let caron = GreekVariantScript::with_scheme("hacek+zwnj".parse().unwrap(), Scheme::V1);
+        let tables = serde_json::from_str::<serde_json::Value>(&caron.tables_json()).unwrap();
+        assert_eq!(tables["reading"]["σ̌"], serde_json::json!(["Sh"]));
+        assert_eq!(tables["break"], "\u{200c}");
     }
 
     //  #[test]
     // fn test_isol() {
     //     use super::Sound::*;
 
-    // assert_eq!( to_greek(&vec![J,A,K]), "άκ");
+    // assert_eq!( to_greek(&[J,A,K]), "άκ");
     // }
 }
@@ -0,0 +1,380 @@
+//! Broad phonemic transcription: IPA (`ʂkɔwa`) and its ASCII form X-SAMPA (`s`kOwa`).
+//!
+//! Transcriptions have no case and no spelling, so writing one and reading it
+//! back gives the sounds, not the original spelling: `ó` comes back as `u`
+//! and `rz` as `ż`.
+
+use crate::official::{Text, TextRepr};
+use crate::{CapitalisationMode, Error, Script, Sound};
+
+/// IPA as a [`Script`].
+#[derive(Debug, Clone, Copy, Default)]
+pub struct IpaScript;
+
+/// X-SAMPA as a [`Script`].
+#[derive(Debug, Clone, Copy, Default)]
+pub struct XSampaScript;
+
+impl Script for IpaScript {
+    fn name(&self) -> &str {
+        "ipa"
+    }
+
+    fn description(&self) -> &str {
+        "IPA"
+    }
+
+    fn decode(&self, input: &str) -> Text {
+        utf8_ipa_to_text(input)
+    }
+
+    fn try_decode(&self, input: &str) -> Result<Text, Error> {
+        try_utf8_ipa_to_text(input)
+    }
+
+    fn encode_word(&self, sounds: &[Sound]) -> String {
+        to_ipa(sounds)
+    }
+
+    fn has_case(&self) -> bool {
+        false
+    }
+}
+
+impl Script for XSampaScript {
+    fn name(&self) -> &str {
+        "x-sampa"
+    }
+
+    fn aliases(&self) -> &[&str] {
+        &["xsampa"]
+    }
+
+    fn description(&self) -> &str {
+        "X-SAMPA"
+    }
+
+    fn decode(&self, input: &str) -> Text {
+        utf8_x_sampa_to_text(input)
+    }
+
+    fn try_decode(&self, input: &str) -> Result<Text, Error> {
+        try_utf8_x_sampa_to_text(input)
+    }
+
+    fn encode_word(&self, sounds: &[Sound]) -> String {
+        to_x_sampa(sounds)
+    }
+
+    fn has_case(&self) -> bool {
+        false
+    }
+}
+
+/// Every sound with its IPA and X-SAMPA symbol.
+const SYMBOLS: [(Sound, &str, &str); 42] = {
+    use Sound::*;
+    [
+        (A, "a", "a"),
+        (B, "b", "b"),
+        (C, "t͡s", "ts"),
+        (Ch, "t͡ʂ", "ts`"),
+        (D, "d", "d"),
+        (Dx, "d͡ʑ", "dz\\"),
+        (Dz, "d͡z", "dz"),
+        (Dh, "d͡ʐ", "dz`"),
+        (E, "ɛ", "E"),
+        (Ex, "ɛ̃", "E~"),
+        (F, "f", "f"),
+        (G, "ɡ", "g"),
+        (H, "x", "x"),
+        (Hx, "x", "x"),
+        (I, "i", "i"),
+        (J, "j", "j"),
+        (K, "k", "k"),
+        (L, "l", "l"),
+        (Lx, "w", "w"),
+        (M, "m", "m"),
+        (N, "n", "n"),
+        (Nx, "ɲ", "J"),
+        (O, "ɔ", "O"),
+        (Ox, "ɔ̃", "O~"),
+        (Ou, "u", "u"),
+        (P, "p", "p"),
+        (Q, "k", "k"),
+        (R, "r", "r"),
+        (Rx, "ʐ", "z`"),
+        (S, "s", "s"),
+        (Sx, "ɕ", "s\\"),
+        (Sh, "ʂ", "s`"),
+        (T, "t", "t"),
+        (Tx, "t͡ɕ", "ts\\"),
+        (U, "u", "u"),
+        (V, "v", "v"),
+        (W, "v", "v"),
+        (X, "ks", "ks"),
+        (Y, "ɨ", "1"),
+        (Z, "z", "z"),
+        (Zx, "ʑ", "z\\"),
+        (Zh, "ʐ", "z`"),
+    ]
+};
+
+/// What the decoder reads besides [`SYMBOLS`]: spellings found in dictionaries.
+/// Entries earlier in this list win over [`SYMBOLS`] when the same symbol
+/// stands for more than one sound.
+const IPA_VARIANTS: &[(&str, &[Sound])] = {
+    use Sound::*;
+    &[
+        ("u", &[U]),
+        ("ʐ", &[Zh]),
+        ("v", &[W]),
+        ("ks", &[K, S]),
+        ("ts", &[C]),
+        ("ʦ", &[C]),
+        ("tʂ", &[Ch]),
+        ("tʃ", &[Ch]),
+        ("t͡ʃ", &[Ch]),
+        ("ʧ", &[Ch]),
+        ("tɕ", &[Tx]),
+        ("ʨ", &[Tx]),
+        ("dz", &[Dz]),
+        ("ʣ", &[Dz]),
+        ("dʐ", &[Dh]),
+        ("dʒ", &[Dh]),
+        ("d͡ʒ", &[Dh]),
+        ("ʤ", &[Dh]),
+        ("dʑ", &[Dx]),
+        ("ʥ", &[Dx]),
+        ("ʃ", &[Sh]),
+        ("ʒ", &[Zh]),
+        ("g", &[G]),
+        ("ʲ", &[J]),
+        ("ç", &[H]),
+        ("ɣ", &[H]),
+        ("ŋ", &[N]),
+        ("ɾ", &[R]),
+        ("ʋ", &[W]),
+        ("e", &[E]),
+        ("o", &[O]),
+        ("ɛw̃", &[Ex]),
+        ("ɔw̃", &[Ox]),
+        // stress, length and syllable breaks are part of the word but not sounds
+        ("ˈ", &[]),
+        ("ˌ", &[]),
+        ("ː", &[]),
+    ]
+};
+
+const X_SAMPA_VARIANTS: &[(&str, &[Sound])] = {
+    use Sound::*;
+    &[
+        ("u", &[U]),
+        ("z`", &[Zh]),
+        ("v", &[W]),
+        ("ks", &[K, S]),
+        ("t_s", &[C]),
+        ("t_s`", &[Ch]),
+        ("t_s\\", &[Tx]),
+        ("d_z", &[Dz]),
+        ("d_z`", &[Dh]),
+        ("d_z\\", &[Dx]),
+        ("e", &[E]),
+        ("o", &[O]),
+        ("'", &[J]),
+        ("_j", &[J]),
+        ("N", &[N]),
+        ("\"", &[]),
+        ("%", &[]),
+    ]
+};
+
+#[derive(Clone, Copy)]
+enum Notation {
+    Ipa,
+    XSampa,
+}
+
+impl Notation {
+    fn symbol(self, sound: Sound) -> &'static str {
+        let (_, ipa, x_sampa) = SYMBOLS[sound as usize];
+        match self {
+            Notation::Ipa => ipa,
+            Notation::XSampa => x_sampa,
+        }
+    }
+
+    fn variants(self) -> &'static [(&'static str, &'static [Sound])] {
+        match self {
+            Notation::Ipa => IPA_VARIANTS,
+            Notation::XSampa => X_SAMPA_VARIANTS,
+        }
+    }
+
+    /// The longest symbol at the beginning of the input, with its length in `char`s.
+    fn longest_match(self, input: &[char]) -> Option<(&'static [Sound], usize)> {
+        let symbols = SYMBOLS.iter().map(|(sound, ipa, x_sampa)| {
+            let symbol = match self {
+                Notation::Ipa => *ipa,
+                Notation::XSampa => *x_sampa,
+            };
+            (symbol, std::slice::from_ref(sound))
+        });
+        let mut best: Option<(&'static [Sound], usize)> = None;
+        for (symbol, sounds) in self.variants().iter().copied().chain(symbols) {
+            let len = symbol.chars().count();
+            if best.is_some_and(|(_, best_len)| best_len >= len) {
+                continue;
+            }
+            if input.len() >= len && input.iter().zip(symbol.chars()).all(|(&a, b)| a == b) {
+                best = Some((sounds, len));
+            }
+        }
+        best
+    }
+
+    fn write(self, input: &[Sound]) -> String {
+        input.iter().map(|&s| self.symbol(s)).collect()
+    }
+
+    fn read(self, input: &str) -> (Text, Option<Error>) {
+        let chars = input.chars().collect::<Vec<char>>();
+        let mut parts = vec![];
+        let mut error = None;
+        let mut i = 0;
+
+        while i < chars.len() {
+            let mut word = vec![];
+            let mut j = i;
+            while let Some((sounds, len)) = self.longest_match(&chars[j..]) {
+                word.extend_from_slice(sounds);
+                j += len;
+            }
+            if j > i {
+                parts.push(TextRepr::Word(word, CapitalisationMode::Lowercase));
+                i = j;
+                continue;
+            }
+
+            while j < chars.len() && self.longest_match(&chars[j..]).is_none() {
+                if chars[j].is_alphabetic() && error.is_none() {
+                    error = Some(Error::UnknownLetter {
+                        offset: j,
+                        ch: chars[j],
+                    });
+                }
+                j += 1;
+            }
+            parts.push(TextRepr::Arbitrary(chars[i..j].iter().collect()));
+            i = j;
+        }
+
+        (Text { parts }, error)
+    }
+}
+
+/// Writes a word given as [`Sound`]s in IPA.
+pub fn to_ipa(input: &[Sound]) -> String {
+    Notation::Ipa.write(input)
+}
+
+/// Writes a word given as [`Sound`]s in X-SAMPA.
+pub fn to_x_sampa(input: &[Sound]) -> String {
+    Notation::XSampa.write(input)
+}
+
+/// Parses broad IPA into the intermediate representation.
+///
+/// Never fails: symbols that cannot be read are kept in place as [`TextRepr::Arbitrary`].
+pub fn utf8_ipa_to_text(input: &str) -> Text {
+    Notation::Ipa.read(input).0
+}
+
+/// Like [`utf8_ipa_to_text`], but fails on the first letter that cannot be read.
+pub fn try_utf8_ipa_to_text(input: &str) -> Result<Text, Error> {
+    match Notation::Ipa.read(input) {
+        (text, None) => Ok(text),
+        (_, Some(error)) => Err(error),
+    }
+}
+
+/// Parses X-SAMPA into the intermediate representation.
+///
+/// Never fails: symbols that cannot be read are kept in place as [`TextRepr::Arbitrary`].
+pub fn utf8_x_sampa_to_text(input: &str) -> Text {
+    Notation::XSampa.read(input).0
+}
+
+/// Like [`utf8_x_sampa_to_text`], but fails on the first letter that cannot be read.
+pub fn try_utf8_x_sampa_to_text(input: &str) -> Result<Text, Error> {
+    match Notation::XSampa.read(input) {
+        (text, None) => Ok(text),
+        (_, Some(error)) => Err(error),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::convert;
+    use crate::greek::GreekScript;
+    use crate::official::OfficialScript;
+
+    #[test]
+    fn symbols_in_sound_order() {
+        for (i, (sound, _, _)) in SYMBOLS.iter().enumerate() {
+            assert_eq!(Sound::ALL[i], *sound);
+        }
+    }
+
+    #[test]
+    fn official_to_transcription() {
+        assert_eq!(
+            convert("Chrząszcz brzmi w trzcinie", &OfficialScript, &IpaScript),
+            "xʐɔ̃ʂt͡ʂ bʐmi v tʐt͡ɕiɲɛ"
+        );
+        assert_eq!(
+            convert("Chrząszcz brzmi w trzcinie", &OfficialScript, &XSampaScript),
+            "xz`O~s`ts` bz`mi v tz`ts\\iJE"
+        );
+        assert_eq!(
+            convert("Dżdżownica, źdźbło!", &OfficialScript, &IpaScript),
+            "d͡ʐd͡ʐɔvɲit͡sa, ʑd͡ʑbwɔ!"
+        );
+        assert_eq!(
+            convert("Volvo taxi", &OfficialScript, &IpaScript),
+            "vɔlvɔ taksi"
+        );
+    }
+
+    #[test]
+    fn transcription_to_official() {
+        assert_eq!(
+            convert("[ˈʂkɔwa] /t͡ɕɛˈplutkɔ/", &IpaScript, &OfficialScript),
+            "[szkoła] /cieplutko/"
+        );
+        assert_eq!(
+            convert("tɕɛplutkɔ ʐɨʨɛ", &IpaScript, &OfficialScript),
+            "cieplutko życie"
+        );
+        assert_eq!(
+            convert("s`kOwa \"ts\\Eplutko", &XSampaScript, &OfficialScript),
+            "szkoła cieplutko"
+        );
+        assert_eq!(convert("ɕɛdɛm", &IpaScript, &GreekScript), "σέδεμ");
+        assert_eq!(
+            convert("vɔlvɔ taksi", &IpaScript, &OfficialScript),
+            "wolwo taksi"
+        );
+    }
+
+    #[test]
+    fn unknown_symbols() {
+        assert_eq!(
+            try_utf8_ipa_to_text("kɔt θ"),
+            Err(Error::UnknownLetter {
+                offset: 4, ch: 'θ'
+            })
+        );
+    }
+}
@@ -0,0 +1,214 @@
+//! What the official spelling does not show: where `i` is a vowel and where words are joined.
+//!
+//! In native words `i` before a vowel only softens the consonant before it:
+//! `pies` is `[P, J, E, S]`. Borrowed words keep it as a full `i`: `biologia`
+//! is `[B, I, O, ...]` and `Maria` is `[M, A, R, I, A]`.
+//!
+//! Likewise `rz` and `dz` are usually one sound, but not in `mar|znąć` or `pod|zielić`,
+//! where the letters belong to different parts of the word.
+//!
+//! Which reading applies cannot be told from the letters alone, so it is decided here, per word.
+
+use std::sync::LazyLock;
+
+/// Word beginnings in which every `i` before a vowel is syllabic: bio-logia, fiol-et.
+const PREFIXES: &[&str] = &["bio", "fiol", "hien", "kiosk", "pian", "pion", "wiol"];
+
+/// Word endings in which every `i` before a vowel is syllabic: mil-ion, akwar-ium.
+const SUFFIXES: &[&str] = &[
+    "ion", "iony", "ionów", "ium", "iusz", "iusza", "iuszem", "iuszowi", "iuszu",
+];
+
+/// Word beginnings in which a prefix ends in a consonant that does not join the next letter,
+/// split at `|`: pod|zielić, od|żywka, bez|imienny. Only these are split, as most words
+/// starting the same way are not prefixed: na|dzieja, po|dział, prze|dział.
+const PREFIXED: &[&str] = &[
+    "bez|i",
+    "nad|ziem",
+    "nad|zor",
+    "nad|zw",
+    "nad|zór",
+    "od|zew",
+    "od|zn",
+    "od|zw",
+    "od|zy",
+    "od|ży",
+    "pod|zam",
+    "pod|zbi",
+    "pod|zesp",
+    "pod|ziel",
+    "pod|ziem",
+    "pod|żeg",
+    "przed|zim",
+    "przed|zjazd",
+    "przed|żniw",
+    "roz|i",
+];
+
+/// Letters that belong to different parts of a word, split at `|`: mar|znąć, tar|zać.
+const SPLITS: &[&str] = &["er|zac", "mar|zł", "mar|zn", "tar|za"];
+
+/// [`PREFIXED`] and [`SPLITS`] as letters, each with where it is split, so that no word
+/// has to wait for the lists to be taken apart again.
+static PREFIXED_LETTERS: LazyLock<Vec<(Vec<char>, usize)>> = LazyLock::new(|| split(PREFIXED));
+static SPLIT_LETTERS: LazyLock<Vec<(Vec<char>, usize)>> = LazyLock::new(|| split(SPLITS));
+
+/// The letters of every entry of a list, and how many of them come before the `|`.
+fn split(list: &[&str]) -> Vec<(Vec<char>, usize)> {
+    list.iter()
+        .map(|split| {
+            let (before, after) = split.split_once('|').unwrap_or((split, ""));
+            (
+                before.chars().chain(after.chars()).collect(),
+                before.chars().count(),
+            )
+        })
+        .collect()
+}
+
+/// Words in which `j` after the prefix `ob` is written as it is, not as a softening `i`:
+/// ob|jaw but obiad.
+const JOINED_AFTER_OB: &[&str] = &[
+    "objaw",
+    "objazd",
+    "objaśni",
+    "objechać",
+    "objeżdż",
+    "objeść",
+    "objąć",
+    "objął",
+    "objęł",
+    "objęci",
+    "objęt",
+];
+
+/// Consonants after which a softening `i` is never written, so `i` is always syllabic:
+/// radio, Maria, patio.
+const ALWAYS_SYLLABIC_AFTER: &[char] = &['d', 'r', 't'];
+
+/// Whether the `i` at `at` in a lowercase word is syllabic rather than softening.
+///
+/// Only meaningful for an `i` between a consonant and a vowel.
+pub(crate) fn syllabic_i(word: &[char], at: usize) -> bool {
+    if at == 0 || word.get(at) != Some(&'i') {
+        return false;
+    }
+    if ALWAYS_SYLLABIC_AFTER.contains(&word[at - 1]) {
+        return true;
+    }
+
+    let covers = |start: usize, affix: &str| {
+        (start..start + affix.chars().count()).contains(&at) && starts_with(word, start, affix)
+    };
+    PREFIXES.iter().any(|prefix| covers(0, prefix))
+        || SUFFIXES.iter().any(|suffix| {
+            let len = suffix.chars().count();
+            word.len() >= len && covers(word.len() - len, suffix)
+        })
+}
+
+/// Positions in a lowercase word between letters that must not be read together,
+/// in increasing order.
+pub(crate) fn morpheme_boundaries(word: &[char]) -> Vec<usize> {
+    let mut boundaries = vec![];
+    for (letters, at) in PREFIXED_LETTERS.iter() {
+        if word.first() == letters.first() && word.starts_with(letters) {
+            boundaries.push(*at);
+        }
+    }
+
+    for (letters, at) in SPLIT_LETTERS.iter() {
+        for start in 0..word.len() {
+            if word[start] == letters[0] && word[start..].starts_with(letters) {
+                boundaries.push(start + at);
+            }
+        }
+    }
+
+    boundaries.sort_unstable();
+    boundaries.dedup();
+    boundaries
+}
+
+/// Writes back the `j` of the words in [`JOINED_AFTER_OB`] in a lowercase word written
+/// with the softening `i`: `obiaw` becomes `objaw`.
+pub(crate) fn respell_j(written: &mut String) {
+    if !written.contains("obi") {
+        return;
+    }
+    for word in JOINED_AFTER_OB {
+        let softened = word.replacen("obj", "obi", 1);
+        if written.contains(&softened) {
+            *written = written.replace(&softened, word);
+        }
+    }
+}
+
+/// Whether `part` is found in `word` at `start`.
+fn starts_with(word: &[char], start: usize, part: &str) -> bool {
+    let mut letters = word.iter().skip(start);
+    part.chars().all(|c| letters.next() == Some(&c))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn syllabic(word: &str) -> Vec<usize> {
+        let word = word.chars().collect::<Vec<char>>();
+        (0..word.len()).filter(|&i| syllabic_i(&word, i)).collect()
+    }
+
+    #[test]
+    fn rules() {
+        assert_eq!(syllabic("biologia"), [1]);
+        assert_eq!(syllabic("maria"), [3]);
+        assert_eq!(syllabic("kolegium"), [5]);
+        assert_eq!(syllabic("wiolonczela"), [1]);
+        assert_eq!(syllabic("milion"), [3]);
+        assert!(syllabic("wiosna").is_empty());
+        assert!(syllabic("pies").is_empty());
+        assert!(syllabic("magia").is_empty());
+    }
+
+    fn boundaries(word: &str) -> Vec<usize> {
+        morpheme_boundaries(&word.chars().collect::<Vec<char>>())
+    }
+
+    #[test]
+    fn prefixes_and_splits() {
+        assert_eq!(boundaries("podzielić"), [3]);
+        assert_eq!(boundaries("podziemie"), [3]);
+        assert_eq!(boundaries("przedżniwny"), [5]);
+        assert_eq!(boundaries("odżywka"), [2]);
+        assert_eq!(boundaries("bezimienny"), [3]);
+        for word in [
+            "odzież",
+            "podziw",
+            "nadzieja",
+            "nadziać",
+            "podział",
+            "podziać",
+            "przedział",
+            "przedziwny",
+            "przedzierać",
+        ] {
+            assert!(boundaries(word).is_empty(), "{word}");
+        }
+        assert_eq!(boundaries("zamarznąć"), [5]);
+        assert!(boundaries("rzeka").is_empty());
+        assert!(boundaries("od").is_empty());
+    }
+
+    #[test]
+    fn j_after_ob() {
+        let respelled = |written: &str| {
+            let mut written = written.to_string();
+            respell_j(&mut written);
+            written
+        };
+        assert_eq!(respelled("obiaw"), "objaw");
+        assert_eq!(respelled("nieobiaśniony"), "nieobjaśniony");
+        assert_eq!(respelled("obiadami"), "obiadami");
+    }
+}
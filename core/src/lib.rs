@@ -1,75 +1,247 @@
-mod greek;
-mod official;
+//! Translator between ways of writing Polish.
+//!
+//! Every supported orthography goes through a common intermediate
+//! representation: a [`Text`] made of [`TextRepr::Word`]s (a sequence of
+//! [`Sound`]s plus the [`CapitalisationMode`] of the original word) and
+//! [`TextRepr::Arbitrary`] runs of text that is copied verbatim.
+//!
+//! ```
+//! use beciarz_core::{greek, official, Sound, TextRepr};
+//!
+//! let text = official::parse("Miód");
+//! let TextRepr::Word(sounds, _) = &text.parts[0] else { unreachable!() };
+//! assert_eq!(sounds, &[Sound::M, Sound::J, Sound::Ou, Sound::D]);
+//! assert_eq!(greek::to_greek(sounds), "μόυδ");
+//! assert_eq!(official::to_official_utf8(sounds), "miód");
+//! ```
 
+mod align;
+mod converter;
+#[cfg(feature = "script-files")]
+pub mod custom;
+pub mod cyrillic;
+mod error;
+pub mod greek;
+pub mod ipa;
+mod lexicon;
+pub mod official;
+pub mod phonetics;
+pub mod rules;
+mod script;
+mod softening;
+mod trie;
+
+pub use align::{Alignment, Span, convert_aligned};
+pub use converter::{CasePolicy, Converter, ConverterBuilder, UnknownPolicy};
+pub use error::Error;
+pub use official::{Text, TextRepr};
+pub use script::{
+    Registry, RoundtripFailure, Script, convert, convert_lossless, convert_pronounced, try_convert,
+    try_convert_pronounced, verify_roundtrip,
+};
+
+use std::fmt;
+use std::str::FromStr;
+
+/// Translates text written in the official Polish orthography into the Greek script.
+///
+/// Never fails: whatever cannot be translated is kept in place.
 pub fn official_to_greek(input: &str) -> String {
-    let text = official::parse(input);
-    text.parts
-        .iter()
-        .map(|part| match part {
-            official::TextRepr::Word(sounds, capitalisation_mode) => {
-                let lowercase_result = greek::to_greek(sounds);
-                capitalisation_mode.apply(&lowercase_result)
-            }
-            official::TextRepr::Arbitrary(text) => text.clone(),
-        })
-        .collect::<Vec<_>>()
-        .join("")
+    convert(input, &official::OfficialScript, &greek::GreekScript)
 }
 
+/// Translates text written in the Greek script into the official Polish orthography.
+///
+/// Never fails: whatever cannot be translated is kept in place.
 pub fn greek_to_official(input: &str) -> String {
-    let text = greek::utf8_greek_to_text(input);
-
-    text.parts
-        .iter()
-        .map(|part| match part {
-            official::TextRepr::Word(sounds, capitalisation_mode) => {
-                let lowercase_result = official::to_official_utf8(sounds);
-                capitalisation_mode.apply(&lowercase_result)
-            }
-            official::TextRepr::Arbitrary(text) => text.clone(),
-        })
-        .collect::<Vec<_>>()
-        .join("")
+    convert(input, &greek::GreekScript, &official::OfficialScript)
+}
+
+/// Like [`official_to_greek`], but fails on the first letter that cannot be translated.
+pub fn try_official_to_greek(input: &str) -> Result<String, Error> {
+    try_convert(input, &official::OfficialScript, &greek::GreekScript)
+}
+
+/// Like [`greek_to_official`], but fails on the first letter that cannot be translated.
+pub fn try_greek_to_official(input: &str) -> Result<String, Error> {
+    try_convert(input, &greek::GreekScript, &official::OfficialScript)
+}
+
+/// [`char::is_alphabetic`], but quicker for the cased letters words are mostly made of.
+fn is_alphabetic(c: char) -> bool {
+    c.is_lowercase() || c.is_uppercase() || c.is_alphabetic()
+}
+
+/// Lowercases a single character without changing the length of the text,
+/// so that positions in the folded text match positions in the original.
+pub(crate) fn fold_case(c: char) -> char {
+    if c.is_ascii() {
+        return c.to_ascii_lowercase();
+    }
+    if c.is_lowercase() {
+        return c;
+    }
+    let mut lowercase = c.to_lowercase();
+    match (lowercase.next(), lowercase.next()) {
+        (Some(lower), None) => lower,
+        _ => c,
+    }
+}
+
+/// For sounds read together from `consumed` letters starting at `start`, the letter each sound
+/// comes from: the first sound from the first letter, the others from the last ones
+/// (`cia` is `[Tx, A]`, read from `c` and `a`).
+pub(crate) fn step_origins(
+    start: usize,
+    consumed: usize,
+    sounds: usize,
+) -> impl Iterator<Item = usize> {
+    (0..sounds).map(move |k| match k {
+        0 => start,
+        k => (start + consumed + k).saturating_sub(sounds).max(start),
+    })
 }
 
-#[derive(PartialEq, Eq, Debug, Clone, Copy)]
-enum CapitalisationMode {
+/// How a word was capitalised in the source text.
+#[derive(PartialEq, Eq, Debug, Clone)]
+pub enum CapitalisationMode {
+    /// `słowo`
     Lowercase,
+    /// `SŁOWO`
     Uppercase,
+    /// `Słowo`
     Titlecase,
+    /// `McDonald`, `iPhone`: whether each [`Sound`] of the word was written in uppercase.
+    Mixed(Vec<bool>),
 }
 impl CapitalisationMode {
-    fn apply(self, lowercase_input: &str) -> String {
+    /// Capitalises a lowercase word according to this mode.
+    ///
+    /// [`CapitalisationMode::Mixed`] is applied letter by letter here; [`Script::encode`]
+    /// applies it sound by sound instead.
+    pub fn apply(&self, lowercase_input: &str) -> String {
+        let mut result = lowercase_input.to_string();
+        self.apply_from(&mut result, 0);
+        result
+    }
+
+    /// Like [`CapitalisationMode::apply`], but capitalises the lowercase word written
+    /// in `text` from `start` on, in place.
+    pub(crate) fn apply_from(&self, text: &mut String, start: usize) {
+        let word = &text[start..];
         match self {
-            CapitalisationMode::Lowercase => lowercase_input.to_string(),
-            CapitalisationMode::Uppercase => lowercase_input.to_uppercase(),
+            CapitalisationMode::Lowercase => {}
+            CapitalisationMode::Uppercase if word.is_ascii() => {
+                text[start..].make_ascii_uppercase()
+            }
+            CapitalisationMode::Uppercase => {
+                let uppercase = word.to_uppercase();
+                text.replace_range(start.., &uppercase);
+            }
+            // the first letter, after any marks the script starts the word with: 'Ίμ
             CapitalisationMode::Titlecase => {
-                let mut c = lowercase_input.chars();
-                match c.next() {
-                    None => String::new(),
-                    Some(f) => f.to_uppercase().chain(c).collect(),
-                }
+                let Some((at, first)) = word.char_indices().find(|&(_, c)| is_alphabetic(c)) else {
+                    return;
+                };
+                let at = start + at;
+                let uppercase = first.to_uppercase().collect::<String>();
+                text.replace_range(at..at + first.len_utf8(), &uppercase);
+            }
+            CapitalisationMode::Mixed(mask) => {
+                let capitalised = word
+                    .chars()
+                    .enumerate()
+                    .flat_map(|(i, c)| match mask.get(i) {
+                        Some(true) => c.to_uppercase().collect::<Vec<_>>(),
+                        _ => vec![c],
+                    })
+                    .collect::<String>();
+                text.replace_range(start.., &capitalised);
             }
         }
     }
 
-    fn detect(input: &[char]) -> Self {
-        if input.is_empty() {
-            return CapitalisationMode::Lowercase;
+    /// Reads a break marker that ends a word as the mark [`convert_lossless`] puts after
+    /// a word of one letter in capitals, given that word as it was written: `Ά\` is `JA`.
+    /// Returns whether it was one.
+    pub(crate) fn read_capitals_mark(&mut self, word: &[char]) -> bool {
+        let letters = word.iter().filter(|&&c| is_alphabetic(c)).count();
+        if *self == CapitalisationMode::Titlecase && letters == 1 {
+            *self = CapitalisationMode::Uppercase;
+            return true;
+        }
+        false
+    }
+
+    /// The capitalisation of the sounds `range` of a word split into parts.
+    pub(crate) fn part(&self, range: std::ops::Range<usize>) -> Self {
+        match self {
+            CapitalisationMode::Titlecase if range.start > 0 => CapitalisationMode::Lowercase,
+            CapitalisationMode::Mixed(mask) => {
+                CapitalisationMode::Mixed(mask.get(range).unwrap_or_default().to_vec())
+            }
+            mode => mode.clone(),
         }
+    }
+
+    /// Detects the capitalisation of a word as written in the source text.
+    ///
+    /// Only letters count, so the marks some scripts use inside words (`'`, `\\`) do not matter.
+    pub fn detect(input: &[char]) -> Self {
+        let mut letters = input.iter().filter(|&&c| is_alphabetic(c));
+        let Some(first) = letters.next() else {
+            return CapitalisationMode::Lowercase;
+        };
 
-        if input.iter().all(|c| c.is_uppercase()) {
+        // a single capital letter starts a word more often than it ends a shouted one
+        let mut rest = letters.peekable();
+        if rest.peek().is_some() && first.is_uppercase() && rest.all(|c| c.is_uppercase()) {
             return CapitalisationMode::Uppercase;
         }
 
-        let mut it = input.iter();
-        let fst = it.next().unwrap();
-        if fst.is_uppercase() {
+        if first.is_uppercase() {
             return CapitalisationMode::Titlecase;
         }
 
         CapitalisationMode::Lowercase
     }
+
+    /// Like [`CapitalisationMode::detect`], but keeps the case of every sound when the word
+    /// is not capitalised in one of the usual ways. The `k`-th of `origins` is the position
+    /// in `input` of the letter sound `k` was read from.
+    pub fn detect_per_sound(input: &[char], origins: impl IntoIterator<Item = usize>) -> Self {
+        // in one pass, as every word is read through here
+        let (mut letters, mut uppercase, mut lowercase_after_first) = (0, 0, 0);
+        let mut first_uppercase = false;
+        for &c in input {
+            let (lower, upper) = (c.is_lowercase(), c.is_uppercase());
+            if !lower && !upper && !c.is_alphabetic() {
+                continue;
+            }
+            if letters == 0 {
+                first_uppercase = upper;
+            } else if lower {
+                lowercase_after_first += 1;
+            }
+            letters += 1;
+            uppercase += usize::from(upper);
+        }
+        let rest_lowercase = lowercase_after_first + 1 >= letters;
+        match (letters, first_uppercase) {
+            (0, _) => return CapitalisationMode::Lowercase,
+            (2.., true) if uppercase == letters => return CapitalisationMode::Uppercase,
+            (_, true) if rest_lowercase => return CapitalisationMode::Titlecase,
+            (_, false) if rest_lowercase => return CapitalisationMode::Lowercase,
+            _ => {}
+        }
+
+        CapitalisationMode::Mixed(
+            origins
+                .into_iter()
+                .map(|origin| input.get(origin).is_some_and(|c| c.is_uppercase()))
+                .collect(),
+        )
+    }
 }
 
 mod test {
@@ -79,6 +251,10 @@ mod test {
             "Litwo ziomek co tam MORDO elo",
             "Λίτβο ζόμεκ τσο ταμ ΜΟΡΔΟ ελό",
         );
+        assert_both_ways("McDonald, iPhone i PiS", "ΜτσΔοναλ'δ, ίΠͱονε ί ΠίΣ");
+        // the letters of a sound are capitalised together, even when written apart
+        assert_both_ways("szCZękA", "ψΘηκΑ");
+        assert_both_ways("CHrząszCZ", "ΧρώψΘ");
     }
 
     #[test]
@@ -91,7 +267,7 @@ mod test {
         );
         assert_both_ways("nie, ni ma", "νέ, νί μα");
         assert_both_ways("przyjdzie kryska na matyska", "πρί'δέ κρισκα να ματισκα");
-        // czy "m" powinno przekazywać zmiękczenie, tzn σμέψνι czy σ'μέψνι?
+        // "m" nie przekazuje zmiękczenia; σμέψνε pisze wariant soft-labials
         assert_both_ways(
             "tukany mają śmieszne dzioby, a wacek ma sklep z masłem",
             "τυκανι μαώ σ'μέψνε δόμπι, α βατσεκ μα σκλέπ ζ μασλεμ",
@@ -128,6 +304,67 @@ mod test {
         assert_both_ways("dziób", "δόυμπ");
 
         assert_both_ways("zjem", "ζ\\έμ");
+
+        // i before a vowel: syllabic in borrowed words, softening otherwise
+        assert_both_ways("biologia", "μπίολόγά");
+        assert_both_ways("Maria i Azja", "Μαρ\\ία ί Αζ\\ά");
+        assert_both_ways("diagnostyka", "δ\\ίαγνοστικα");
+        assert_both_ways("pies", "πέσ");
+        assert_both_ways("lilia", "λίλ'ά");
+        assert_both_ways("radio", "ραδ\\ίο");
+        assert_both_ways("bieg na obiad", "μπέγ να ομπάδ");
+        assert_both_ways("Niebieski objaw", "Νέμπέσκί ομπάβ");
+
+        // digraphs split between the parts of a word
+        assert_both_ways("podzielić", "ποδ\\ζέλίτ'");
+        assert_both_ways("marznąć i tarzać się", "μαρζνωτ' ί ταρζατ' σή");
+        assert_both_ways("odzew, odzież", "οδ\\ζεβ, οδέξ");
+        assert_both_ways("bezimienny", "μπεζ\\ίμέννι");
+        assert_both_ways("poufny", "πο\\υφνι");
+
+        // h and ch are the same sound, but not the same letter
+        assert_both_ways("Herbata i chleb", "Ͱερμπατα ί χλέμπ");
+        assert_both_ways("HOP", "ͰΟΠ");
+
+        // foreign letters stay inside their words
+        assert_both_ways("Volvo i taxi", "Ϝολ'ϝο ί ταϗί");
+        assert_both_ways("Xawery w aquaparku", "Ϗαβερι β αϙυαπαρκυ");
+        assert_both_ways("Thomas", "Τͱομασ");
+    }
+
+    #[test]
+    fn errors() {
+        use super::Error;
+        assert_eq!(
+            super::try_official_to_greek("ala ma ßkota"),
+            Err(Error::UnknownLetter {
+                offset: 7, ch: 'ß'
+            })
+        );
+        assert_eq!(super::official_to_greek("ala ma ßkota"), "αλά μα ßκοτα");
+        assert_eq!(
+            super::try_greek_to_official("ανά, ala"),
+            Err(Error::UnknownLetter { offset: 5, ch: 'a' })
+        );
+        assert_eq!(super::greek_to_official("ανά, ala"), "ania, ala");
+        assert_eq!(
+            super::try_official_to_greek("ania!"),
+            Ok("ανά!".to_string())
+        );
+
+        // 'İ' lowercases to two characters, which used to shift the offsets
+        assert_eq!(super::official_to_greek("İstanbuł İ"), "İστανμπυλ İ");
+    }
+
+    #[test]
+    fn sound_names() {
+        use super::Sound;
+        for sound in Sound::ALL {
+            assert_eq!(sound.to_string().parse::<Sound>(), Ok(sound));
+        }
+        assert_eq!("Sx".parse::<Sound>(), Ok(Sound::Sx));
+        assert!("sx".parse::<Sound>().is_err());
+        assert!("".parse::<Sound>().is_err());
     }
 
     #[allow(dead_code)]
@@ -147,7 +384,7 @@ mod test {
 
         assert_eq!("μέ'", super::official_to_greek("miej"));
         assert_eq!("έ'", super::official_to_greek("jej"));
-        assert_eq!("δ\\άγνοστικα", super::official_to_greek("diagnostyka"));
+        assert_eq!("δ\\ίαγνοστικα", super::official_to_greek("diagnostyka"));
 
         assert_eq!(super::official_to_greek("kiedy"), "κέδι");
         assert_eq!(super::official_to_greek("mięso"), "μήσο");
@@ -170,7 +407,12 @@ mod test {
     }
 }
 
-#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+/// A single unit of the intermediate representation.
+///
+/// Mostly a phoneme of Polish, although a few variants (marked "nie prawda")
+/// exist only to keep the official spelling recoverable.
+/// [`Display`](fmt::Display) and [`FromStr`] use the variant names, e.g. `Sx`.
+#[derive(Copy, Clone, PartialEq, Eq, Hash, Debug)]
 pub enum Sound {
     A,
     B,
@@ -185,6 +427,7 @@ pub enum Sound {
     F,
     G,
     H,
+    Hx, // nie prawda
     I,
     J,
     K,
@@ -197,6 +440,7 @@ pub enum Sound {
     Ox,
     Ou, // nie prawda
     P,
+    Q, // nie prawda
     R,
     Rx, // nie prawda
     S,
@@ -205,7 +449,9 @@ pub enum Sound {
     T,
     Tx,
     U,
+    V, // nie prawda
     W,
+    X, // nie prawda
     Y,
     Z,
     Zx,
@@ -213,6 +459,52 @@ pub enum Sound {
 }
 
 impl Sound {
+    /// Every sound, in declaration order.
+    pub const ALL: [Sound; 42] = [
+        Sound::A,
+        Sound::B,
+        Sound::C,
+        Sound::Ch,
+        Sound::D,
+        Sound::Dx,
+        Sound::Dz,
+        Sound::Dh,
+        Sound::E,
+        Sound::Ex,
+        Sound::F,
+        Sound::G,
+        Sound::H,
+        Sound::Hx,
+        Sound::I,
+        Sound::J,
+        Sound::K,
+        Sound::L,
+        Sound::Lx,
+        Sound::M,
+        Sound::N,
+        Sound::Nx,
+        Sound::O,
+        Sound::Ox,
+        Sound::Ou,
+        Sound::P,
+        Sound::Q,
+        Sound::R,
+        Sound::Rx,
+        Sound::S,
+        Sound::Sx,
+        Sound::Sh,
+        Sound::T,
+        Sound::Tx,
+        Sound::U,
+        Sound::V,
+        Sound::W,
+        Sound::X,
+        Sound::Y,
+        Sound::Z,
+        Sound::Zx,
+        Sound::Zh,
+    ];
+
     fn is_softened(self) -> bool {
         matches!(
             self,
@@ -247,3 +539,32 @@ impl Sound {
     //     return self.is_softened();
     // }
 }
+
+impl fmt::Display for Sound {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(self, f)
+    }
+}
+
+/// Returned when a string does not name any [`Sound`].
+#[derive(PartialEq, Eq, Debug, Clone)]
+pub struct ParseSoundError(String);
+
+impl fmt::Display for ParseSoundError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "unknown sound: {:?}", self.0)
+    }
+}
+
+impl std::error::Error for ParseSoundError {}
+
+impl FromStr for Sound {
+    type Err = ParseSoundError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Sound::ALL
+            .into_iter()
+            .find(|sound| sound.to_string() == s)
+            .ok_or_else(|| ParseSoundError(s.to_string()))
+    }
+}
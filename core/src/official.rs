@@ -1,60 +1,149 @@
-use crate::CapitalisationMode;
+//! The official Polish orthography: `pozdrawiam cieplutko`.
+
+use std::sync::LazyLock;
+
+use crate::lexicon;
+use crate::rules::Rules;
+use crate::trie::Trie;
+use crate::{CapitalisationMode, Error, Script, fold_case};
 
 use super::Sound;
 use super::Sound::*;
 
-fn naive_to_string(s: Sound) -> &'static str {
-    use Sound::*;
-
-    (match s {
-        A => "a",
-        B => "b",
-        C => "c",
-        D => "d",
-        E => "e",
-        F => "f",
-        G => "g",
-        H => "ch", // bo częstsze
-        I => "i",
-        J => "j",
-        K => "k",
-        L => "l",
-        Lx => "ł",
-        M => "m",
-        N => "n",
-        Nx => "ń",
-        O => "o",
-        Ou => "ó",
-        Ox => "ą",
-        P => "p",
-        R => "r",
-        Rx => "rz",
-        S => "s",
-        Sx => "ś",
-        Sh => "sz",
-        T => "t",
-        Tx => "ć",
-        U => "u",
-        W => "w",
-        Y => "y",
-        Z => "z",
-        Zx => "ź",
-        Zh => "ż",
-        Ch => "cz",
-        Dz => "dz",
-        Dx => "dź",
-        Dh => "dż",
-        Ex => "ę",
-    }) as _
+/// How Polish sounds are written in the official orthography.
+static WRITING: LazyLock<Rules> = LazyLock::new(|| {
+    Rules::parse(include_str!("../rules/official.rules")).expect("the official rules are valid")
+});
+
+/// The official orthography as a [`Script`].
+#[derive(Debug, Clone, Copy, Default)]
+pub struct OfficialScript;
+
+impl Script for OfficialScript {
+    fn name(&self) -> &str {
+        "oficjalny"
+    }
+
+    fn aliases(&self) -> &[&str] {
+        &["o"]
+    }
+
+    fn description(&self) -> &str {
+        "Official / Oficjalny"
+    }
+
+    fn decode(&self, input: &str) -> Text {
+        parse(input)
+    }
+
+    fn try_decode(&self, input: &str) -> Result<Text, Error> {
+        try_parse(input)
+    }
+
+    fn decode_lossless(&self, input: &str) -> Text {
+        parse_lossless(input)
+    }
+
+    fn encode_word(&self, sounds: &[Sound]) -> String {
+        to_official_utf8(sounds)
+    }
+
+    fn try_encode_word(&self, sounds: &[Sound]) -> Result<String, Error> {
+        try_to_official_utf8(sounds)
+    }
 }
 
-pub fn parse(input_: &str) -> Text {
-    let case_preserving_input = input_.chars().collect::<Vec<char>>();
-    let input = input_.to_lowercase(); // TODO handle uppercase
+/// Parses text written in the official orthography into the intermediate representation.
+///
+/// Never fails: letters that cannot be read are kept in place as [`TextRepr::Arbitrary`].
+pub fn parse(input: &str) -> Text {
+    parse_recovering(input, false).0
+}
+
+/// Like [`parse`], but a word whose spelling does not come back from its sounds
+/// (`kja` is written back as `kia`) is split into adjacent words that do: `kj` + `a`.
+pub fn parse_lossless(input: &str) -> Text {
+    parse_recovering(input, true).0
+}
+
+/// Like [`parse`], but fails on the first letter that cannot be read.
+pub fn try_parse(input: &str) -> Result<Text, Error> {
+    match parse_recovering(input, false) {
+        (text, None) => Ok(text),
+        (_, Some(error)) => Err(error),
+    }
+}
+
+/// Polish letters that Unicode decomposes: the letter, the combining mark and the letter
+/// with the mark. `ł` has no decomposition and is always written as one character.
+const DECOMPOSED: [(char, char, char); 16] = [
+    ('a', '\u{328}', 'ą'),
+    ('e', '\u{328}', 'ę'),
+    ('c', '\u{301}', 'ć'),
+    ('n', '\u{301}', 'ń'),
+    ('o', '\u{301}', 'ó'),
+    ('s', '\u{301}', 'ś'),
+    ('z', '\u{301}', 'ź'),
+    ('z', '\u{307}', 'ż'),
+    ('A', '\u{328}', 'Ą'),
+    ('E', '\u{328}', 'Ę'),
+    ('C', '\u{301}', 'Ć'),
+    ('N', '\u{301}', 'Ń'),
+    ('O', '\u{301}', 'Ó'),
+    ('S', '\u{301}', 'Ś'),
+    ('Z', '\u{301}', 'Ź'),
+    ('Z', '\u{307}', 'Ż'),
+];
+
+/// The input with every letter and combining mark joined into one character.
+fn compose(input: &str) -> Vec<char> {
+    let mut composed = Vec::with_capacity(input.len());
+    let mut chars = input.chars().peekable();
+    while let Some(c) = chars.next() {
+        composed.push(match chars.peek() {
+            // the table is only searched when a combining mark follows
+            Some(&mark) if ('\u{300}'..='\u{36f}').contains(&mark) => {
+                match DECOMPOSED.iter().find(|&&(b, m, _)| b == c && m == mark) {
+                    Some(&(_, _, letter)) => {
+                        chars.next();
+                        letter
+                    }
+                    None => c,
+                }
+            }
+            _ => c,
+        });
+    }
+    composed
+}
+
+/// Where the char at `offset` of the [`compose`]d input is in the input, in `char`s.
+fn position_in(input: &str, offset: usize) -> usize {
+    let mut chars = input.chars().peekable();
+    let mut position = 0;
+    for _ in 0..offset {
+        let c = chars.next();
+        position += 1;
+        if let (Some(c), Some(&mark)) = (c, chars.peek())
+            && DECOMPOSED.iter().any(|&(b, m, _)| b == c && m == mark)
+        {
+            chars.next();
+            position += 1;
+        }
+    }
+    position
+}
+
+fn parse_recovering(input_: &str, lossless: bool) -> (Text, Option<Error>) {
+    let case_preserving_input = compose(input_);
+    let charsi = case_preserving_input
+        .iter()
+        .map(|&c| fold_case(c))
+        .collect::<Vec<char>>();
 
     let mut parts = vec![];
+    let mut error = None;
     let mut i = 0;
-    let charsi = input.chars().collect::<Vec<char>>();
 
     while i < charsi.len() {
         let chars = &charsi[i..];
@@ -62,437 +151,307 @@ pub fn parse(input_: &str) -> Text {
             break;
         }
         let mut j = 0;
-        while j < chars.len() && single_naive(chars[j]).is_none() {
+        while j < chars.len() {
+            // whitespace, by far the most common, is neither a letter nor an unknown one
+            if !chars[j].is_ascii_whitespace() {
+                if is_letter(chars[j]) {
+                    break;
+                }
+                if chars[j].is_alphabetic() && error.is_none() {
+                    error = Some(Error::UnknownLetter {
+                        offset: i + j,
+                        ch: case_preserving_input[i + j],
+                    });
+                }
+            }
             j += 1;
         }
 
         if j > 0 {
-            parts.push(TextRepr::Arbitrary(chars[..j].iter().collect()));
+            parts.push(TextRepr::Arbitrary(
+                case_preserving_input[i..i + j].iter().collect(),
+            ));
             i += j;
             continue;
         }
 
-        let cr = parse_word(chars);
+        let (cr, origins) = parse_word_with_origins(chars);
         if cr.consumed > 0 {
-            parts.push(TextRepr::Word(
-                cr.result,
-                CapitalisationMode::detect(&case_preserving_input[i..i + cr.consumed]),
-            ));
+            let word = &chars[..cr.consumed];
+            if lossless && !writes_back(&cr.result, word) {
+                let mut start = 0;
+                for end in lossless_pieces(word) {
+                    let (piece, origins) = parse_word_with_origins(&word[start..end]);
+                    parts.push(TextRepr::Word(
+                        piece.result,
+                        CapitalisationMode::detect_per_sound(
+                            &case_preserving_input[i + start..i + end],
+                            origins.iter().copied(),
+                        ),
+                    ));
+                    start = end;
+                }
+            } else {
+                parts.push(TextRepr::Word(
+                    cr.result,
+                    CapitalisationMode::detect_per_sound(
+                        &case_preserving_input[i..i + cr.consumed],
+                        origins.iter().copied(),
+                    ),
+                ));
+            }
             i += cr.consumed;
             continue;
         }
 
-        print!("Failed to parse at index {}: '{}'", i, chars[0]);
-        break;
+        // nothing parsed, keep the character as it is
+        error.get_or_insert(Error::UnknownLetter {
+            offset: i,
+            ch: case_preserving_input[i],
+        });
+        parts.push(TextRepr::Arbitrary(case_preserving_input[i].to_string()));
+        i += 1;
+    }
+
+    // back from the composed input to the one given
+    if let Some(Error::UnknownLetter { offset, .. }) = &mut error {
+        *offset = position_in(input_, *offset);
     }
 
-    Text { parts }
+    (Text { parts }, error)
 }
 
-#[derive(PartialEq, Eq, Debug)]
+/// A fragment of a [`Text`].
+#[derive(PartialEq, Eq, Debug, Clone)]
 pub enum TextRepr {
+    /// Anything that is not a word (whitespace, punctuation, digits, ...), copied verbatim.
     Arbitrary(String),
+    /// A word and the way it was capitalised.
     Word(Vec<Sound>, CapitalisationMode),
 }
 
-#[derive(PartialEq, Eq, Debug)]
+/// Text in the intermediate representation, independent of the script it was written in.
+#[derive(PartialEq, Eq, Debug, Clone)]
 pub struct Text {
     pub parts: Vec<TextRepr>,
 }
 
+/// Whether the sounds of a word are written back exactly as the word was spelled.
+fn writes_back(sounds: &[Sound], word: &[char]) -> bool {
+    to_official_utf8(sounds).chars().eq(word.iter().copied())
+}
+
+/// The most letters read as one: `dzia`.
+const LONGEST_GROUP: usize = 4;
+
+/// Where to split a word so that every piece is written back the way it was spelled,
+/// as the end of each piece. Pieces are taken as long as possible, but a piece never ends
+/// more than [`LONGEST_GROUP`] letters after the first letter the rest of the word is not
+/// written back as, so that a long word is not read again for every place it could end.
+fn lossless_pieces(word: &[char]) -> Vec<usize> {
+    let mut ends = vec![];
+    let mut start = 0;
+    while start < word.len() {
+        // how much of the rest is written back, read in ever longer stretches until what is
+        // not written back is far enough from where the stretch was cut
+        let mut stretch = 2 * LONGEST_GROUP;
+        let longest = loop {
+            let piece = &word[start..(start + stretch).min(word.len())];
+            let cr = parse_word(piece);
+            let same = to_official_utf8(&cr.result)
+                .chars()
+                .zip(&piece[..cr.consumed])
+                .take_while(|&(a, &b)| a == b)
+                .count();
+            if start + piece.len() == word.len() || same + LONGEST_GROUP < piece.len() {
+                break (start + same + LONGEST_GROUP).min(word.len());
+            }
+            stretch *= 2;
+        };
+        let end = (start + 1..=longest)
+            .rev()
+            .find(|&end| {
+                let piece = &word[start..end];
+                let cr = parse_word(piece);
+                cr.consumed == piece.len() && writes_back(&cr.result, piece)
+            })
+            .unwrap_or(start + 1);
+        ends.push(end);
+        start = end;
+    }
+    ends
+}
+
 struct ConsumeResult {
     result: Vec<Sound>,
     consumed: usize,
 }
 fn parse_word(input: &[char]) -> ConsumeResult {
+    parse_word_with_origins(input).0
+}
+
+/// Like [`parse_word`], but also tells for every sound which letter of the input it was read from.
+fn parse_word_with_origins(input: &[char]) -> (ConsumeResult, Vec<usize>) {
     let charsi = input;
+    let word_len = input.iter().take_while(|&&c| is_letter(c)).count();
+    let word = &input[..word_len];
+    let boundaries = lexicon::morpheme_boundaries(word);
     let mut i = 0;
     let mut result = vec![];
+    let mut origins = vec![];
     while i < charsi.len() {
-        let chars = &charsi[i..];
-        let cr = try_dzx(chars);
-        if cr.consumed > 0 {
-            result.extend(cr.result);
-            i += cr.consumed;
-            continue;
-        }
-
-        if chars.len() >= 2 && chars[0] == 'd' && chars[1] == 'ż' {
-            result.push(Dh);
-            i += 2;
-            continue;
-        }
-
-        let cr = try_ci_si_zi(chars);
-        if cr.consumed > 0 {
-            result.extend(cr.result);
-            i += cr.consumed;
-            continue;
-        }
-
-        let cr = try_i_samogl(chars);
-        if cr.consumed > 0 {
-            result.extend(cr.result);
-            i += cr.consumed;
-            continue;
-        }
-
-        if let Some((dwuznak_sound, consumed)) = try_dwuznak(chars) {
-            result.push(dwuznak_sound);
-            i += consumed;
-            continue;
-        }
-
-        if let Some(single_sound) = single_naive(charsi[i]) {
-            result.push(single_sound);
-            i += 1;
-            continue;
-        }
-        // nothing found to parse
-        break;
-    }
-
-    ConsumeResult {
-        result,
-        consumed: i,
-    }
-}
-
-// UWAGA TO NIE ZADZIAŁA ZAWSZE
-fn try_i_samogl(input: &[char]) -> ConsumeResult {
-    if input.len() < 2 || input[0] != 'i' {
-        ConsumeResult {
-            result: vec![],
-            consumed: 0,
-        }
-    } else {
-        let i1 = input[1];
-        match i1 {
-            'a' => ConsumeResult {
-                result: vec![J, A],
-                consumed: 2,
-            },
-            'ą' => ConsumeResult {
-                result: vec![J, Ox],
-                consumed: 2,
-            },
-            'e' => ConsumeResult {
-                result: vec![J, E],
-                consumed: 2,
-            },
-            'ę' => ConsumeResult {
-                result: vec![J, Ex],
-                consumed: 2,
-            },
-            'o' => ConsumeResult {
-                result: vec![J, O],
-                consumed: 2,
-            },
-            'ó' => ConsumeResult {
-                result: vec![J, Ou],
-                consumed: 2,
-            },
-            'u' => ConsumeResult {
-                result: vec![J, U],
-                consumed: 2,
-            },
-            _ => ConsumeResult {
-                result: vec![],
-                consumed: 0,
-            },
-        }
-    }
-}
-
-fn try_ci_si_zi(input: &[char]) -> ConsumeResult {
-    if input.len() < 2 {
-        return ConsumeResult {
-            result: vec![],
-            consumed: 0,
+        // nothing is read across a boundary: pod|zielić
+        let end = boundaries
+            .iter()
+            .copied()
+            .find(|&b| b > i)
+            .unwrap_or(charsi.len());
+        let before = result.len();
+        let Some(consumed) = parse_step(&charsi[i..end], word, i, &mut result) else {
+            // nothing found to parse
+            break;
         };
+        origins.extend(crate::step_origins(i, consumed, result.len() - before));
+        i += consumed;
     }
 
-    let i0 = input[0];
-
-    let okk = match i0 {
-        'c' => Some(Tx),
-        's' => Some(Sx),
-        'z' => Some(Zx),
-        'n' => Some(Nx),
-        _ => None,
-    };
-
-    match okk {
-        Some(init_sound) => {
-            if input.len() == 2 {
-                if input[1] == 'i' {
-                    ConsumeResult {
-                        result: vec![init_sound, I],
-                        consumed: 2,
-                    }
-                } else {
-                    ConsumeResult {
-                        result: vec![],
-                        consumed: 0,
-                    }
-                }
-            } else if input[1] == 'i' {
-                let i2 = input[2];
-
-                match i2 {
-                    'a' => ConsumeResult {
-                        result: vec![init_sound, Sound::A],
-                        consumed: 3,
-                    },
-                    'ą' => ConsumeResult {
-                        result: vec![init_sound, Sound::Ox],
-                        consumed: 3,
-                    },
-                    'e' => ConsumeResult {
-                        result: vec![init_sound, Sound::E],
-                        consumed: 3,
-                    },
-                    'ę' => ConsumeResult {
-                        result: vec![init_sound, Sound::Ex],
-                        consumed: 3,
-                    },
-                    'o' => ConsumeResult {
-                        result: vec![init_sound, Sound::O],
-                        consumed: 3,
-                    },
-                    'ó' => ConsumeResult {
-                        result: vec![init_sound, Sound::Ou],
-                        consumed: 3,
-                    },
-                    'u' => ConsumeResult {
-                        result: vec![init_sound, Sound::U],
-                        consumed: 3,
-                    },
-                    _ => ConsumeResult {
-                        result: vec![init_sound, Sound::I],
-                        consumed: 2,
-                    },
-                }
-            } else {
-                ConsumeResult {
-                    result: vec![],
-                    consumed: 0,
-                }
-            }
-        }
-        None => ConsumeResult {
-            result: vec![],
-            consumed: 0,
+    (
+        ConsumeResult {
+            result,
+            consumed: i,
         },
-    }
+        origins,
+    )
 }
 
-fn try_dzx(input: &[char]) -> ConsumeResult {
-    if input.len() < 3 {
-        return ConsumeResult {
-            result: vec![],
-            consumed: 0,
-        };
+/// Reads the sounds at the beginning of `chars`, which starts at `i` in `word`, into `result`.
+/// Returns how many letters were read.
+fn parse_step(chars: &[char], word: &[char], i: usize, result: &mut Vec<Sound>) -> Option<usize> {
+    // the one reading the letters alone do not tell
+    if chars.first() == Some(&'i') && lexicon::syllabic_i(word, i) {
+        result.push(I);
+        return Some(1);
     }
 
-    let i0 = input[0];
-    if i0 != 'd' {
-        return ConsumeResult {
-            result: vec![],
-            consumed: 0,
-        };
-    }
-    let i1 = input[1];
-
-    match (i0, i1) {
-        ('d', 'z') => {
-            if input.len() == 3 && input[2] == 'i' {
-                ConsumeResult {
-                    result: vec![Sound::Dx, Sound::I],
-                    consumed: 3,
-                }
-            } else if input.len() > 3 {
-                let i2 = input[2];
-                let i3 = input[3];
-                if i2 != 'i' {
-                    ConsumeResult {
-                        result: vec![],
-                        consumed: 0,
-                    }
-                } else {
-                    match i3 {
-                        'a' => ConsumeResult {
-                            result: vec![Sound::Dx, Sound::A],
-                            consumed: 4,
-                        },
-                        'ą' => ConsumeResult {
-                            result: vec![Sound::Dx, Sound::Ox],
-                            consumed: 4,
-                        },
-                        'e' => ConsumeResult {
-                            result: vec![Sound::Dx, Sound::E],
-                            consumed: 4,
-                        },
-                        'ę' => ConsumeResult {
-                            result: vec![Sound::Dx, Sound::Ex],
-                            consumed: 4,
-                        },
-                        'o' => ConsumeResult {
-                            result: vec![Sound::Dx, Sound::O],
-                            consumed: 4,
-                        },
-                        'ó' => ConsumeResult {
-                            result: vec![Sound::Dx, Sound::Ou],
-                            consumed: 4,
-                        },
-                        'u' => ConsumeResult {
-                            result: vec![Sound::Dx, Sound::U],
-                            consumed: 4,
-                        },
-                        _ => ConsumeResult {
-                            result: vec![Sound::Dx, Sound::I],
-                            consumed: 3,
-                        },
-                    }
-                }
-            } else {
-                ConsumeResult {
-                    result: vec![],
-                    consumed: 0,
-                }
-            }
-        }
-
-        _ => ConsumeResult {
-            result: vec![],
-            consumed: 0,
-        },
-    }
+    let (sounds, consumed) = GRAPHEMES.longest_match(chars)?;
+    result.extend_from_slice(sounds);
+    Some(consumed)
 }
 
-fn try_dwuznak(input: &[char]) -> Option<(Sound, usize)> {
-    if input.len() < 2 {
-        return None;
-    }
-
-    let i0 = input[0];
-    let i1 = input[1];
-
-    match (i0, i1) {
-        ('c', 'z') => Some((Ch, 2)),
-        ('s', 'z') => Some((Sh, 2)),
-        ('d', 'z') => Some((Dz, 2)),
-        ('d', 'ź') => Some((Dx, 2)),
-        ('r', 'z') => Some((Rx, 2)),
-        ('l', 'x') => Some((Lx, 2)),
-        ('c', 'h') => Some((H, 2)),
-        _ => None,
-    }
+/// Every letter and group of letters, with the sounds it is read as.
+/// The longest group that matches is read: `dzi` before `dz` before `d`.
+const LETTERS: &[(&str, &[Sound])] = &[
+    ("a", &[A]),
+    ("ą", &[Ox]),
+    ("b", &[B]),
+    ("c", &[C]),
+    ("ć", &[Tx]),
+    ("d", &[D]),
+    ("e", &[E]),
+    ("ę", &[Ex]),
+    ("f", &[F]),
+    ("g", &[G]),
+    ("h", &[Hx]),
+    ("q", &[Q]),
+    ("v", &[V]),
+    ("x", &[X]),
+    ("i", &[I]),
+    ("j", &[J]),
+    ("k", &[K]),
+    ("l", &[L]),
+    ("ł", &[Lx]),
+    ("m", &[M]),
+    ("n", &[N]),
+    ("ń", &[Nx]),
+    ("o", &[O]),
+    ("ó", &[Ou]),
+    ("p", &[P]),
+    ("r", &[R]),
+    ("s", &[S]),
+    ("ś", &[Sx]),
+    ("t", &[T]),
+    ("u", &[U]),
+    ("w", &[W]),
+    ("y", &[Y]),
+    ("z", &[Z]),
+    ("ż", &[Zh]),
+    ("ź", &[Zx]),
+    ("cz", &[Ch]),
+    ("sz", &[Sh]),
+    ("dz", &[Dz]),
+    ("dź", &[Dx]),
+    ("dż", &[Dh]),
+    ("rz", &[Rx]),
+    ("lx", &[Lx]),
+    ("ch", &[H]),
+    ("ci", &[Tx, I]),
+    ("si", &[Sx, I]),
+    ("zi", &[Zx, I]),
+    ("ni", &[Nx, I]),
+    ("dzi", &[Dx, I]),
+];
+
+/// Letters ending in an `i` that only softens when a vowel follows, and the sound they
+/// are read as then: `cia` is `[Tx, A]`, `pies` is `[P, J, E, S]`.
+const SOFTENING_I: &[(&str, Sound)] = &[
+    ("ci", Tx),
+    ("si", Sx),
+    ("zi", Zx),
+    ("ni", Nx),
+    ("dzi", Dx),
+    ("i", J),
+];
+
+/// The vowels after which [`SOFTENING_I`] only softens.
+const SOFTENED_VOWELS: &[(char, Sound)] = &[
+    ('a', A),
+    ('ą', Ox),
+    ('e', E),
+    ('ę', Ex),
+    ('o', O),
+    ('ó', Ou),
+    ('u', U),
+];
+
+static GRAPHEMES: LazyLock<Trie<char>> = LazyLock::new(|| {
+    let letters = LETTERS
+        .iter()
+        .map(|&(letters, sounds)| (letters.to_string(), sounds.to_vec()));
+    let softened = SOFTENING_I.iter().flat_map(|&(letters, soft)| {
+        SOFTENED_VOWELS
+            .iter()
+            .map(move |&(vowel, sound)| (format!("{letters}{vowel}"), vec![soft, sound]))
+    });
+    letters
+        .chain(softened)
+        .map(|(letters, sounds)| (letters.chars().collect::<Vec<char>>(), sounds))
+        .collect()
+});
+
+/// Whether a (lowercase) letter is read as a part of a word.
+fn is_letter(c: char) -> bool {
+    GRAPHEMES.starts(c)
 }
 
-fn single_naive(c: char) -> Option<Sound> {
-    match c {
-        'a' => Some(A),
-        'ą' => Some(Ox),
-        'b' => Some(B),
-        'c' => Some(C),
-        'ć' => Some(Tx),
-        'd' => Some(D),
-        'e' => Some(E),
-        'ę' => Some(Ex),
-        'f' => Some(F),
-        'g' => Some(G),
-        'h' => Some(H),
-        'i' => Some(I),
-        'j' => Some(J),
-        'k' => Some(K),
-        'l' => Some(L),
-        'ł' => Some(Lx),
-        'm' => Some(M),
-        'n' => Some(N),
-        'ń' => Some(Nx),
-        'o' => Some(O),
-        'ó' => Some(Ou),
-        'p' => Some(P),
-        'r' => Some(R),
-        's' => Some(S),
-        'ś' => Some(Sx),
-        't' => Some(T),
-        'u' => Some(U),
-        'w' => Some(W),
-        'y' => Some(Y),
-        'z' => Some(Z),
-        'ż' => Some(Zh),
-        'ź' => Some(Zx),
-        _ => None,
-    }
+/// Writes a word given as [`Sound`]s in the official orthography (lowercase).
+///
+/// Never fails: sounds that do not fit their context are written on their own.
+pub fn to_official_utf8(input: &[Sound]) -> String {
+    to_official_recovering(input).0
 }
 
-pub fn to_official_utf8(input_initial: &[Sound]) -> String {
-    let mut res: String = String::new();
-    let mut i = 0;
-
-    while i < input_initial.len() {
-        let input = &input_initial[i..];
-        let c0 = input[0];
-
-        if c0 == Rx {
-            res.push_str("rz");
-            i += 1;
-            continue;
-        }
-
-        if input.len() > 1 {
-            // ći -> ci; śe -> się
-            let i1 = input[1];
-            if (c0.is_softened()) && c0 != I && c0 != L && (i1.is_vowel() && i1 != Y) {
-                // if c0 != Rx {
-                match c0 {
-                    Sound::Sx => res.push('s'),
-                    Sound::Zx => res.push('z'),
-                    Sound::Nx => res.push('n'),
-                    Sound::Tx => res.push('c'),
-                    Sound::Dx => {
-                        res.push('d');
-                        res.push('z');
-                    }
-                    other => panic!("ni wim co to: {other:?}"),
-                }
-                i += 1;
-                if i1 != I {
-                    res.push('i');
-                }
-                continue;
-            }
-
-            // wje ->wie
-            if (c0 == W || c0 == K || c0 == M || c0 == G) && i1 == J && input.len() > 2 {
-                let i2 = input[2];
-                if i2.is_vowel() && i2 != Y && i2 != I {
-                    if c0 == W {
-                        res.push('w');
-                    } else if c0 == K {
-                        res.push('k');
-                    } else if c0 == M {
-                        res.push('m');
-                    } else if c0 == G {
-                        res.push('g');
-                    } else {
-                        panic!("Unexpected sound: {c0:?}");
-                    }
-                    i += 1; // consume W/K
-                    res.push('i');
-                    i += 1; //consuje J
-                    continue; // handle the rest separately
-                }
-            }
-        }
-
-        res.push_str(naive_to_string(c0));
-        i += 1;
+/// Like [`to_official_utf8`], but fails on the first sound that does not fit its context.
+pub fn try_to_official_utf8(input: &[Sound]) -> Result<String, Error> {
+    match to_official_recovering(input) {
+        (text, None) => Ok(text),
+        (_, Some(error)) => Err(error),
     }
+}
 
-    res
+fn to_official_recovering(input: &[Sound]) -> (String, Option<Error>) {
+    let (mut written, error) = WRITING.write_recovering(input);
+    lexicon::respell_j(&mut written);
+    (written, error)
 }
 
 #[cfg(test)]
@@ -505,7 +464,9 @@ mod tests {
         let input = vec![K, O, P, Y, T, K, O];
         assert_eq!(to_official_utf8(&input), "kopytko");
         let input = vec![Ch, A, H, A];
-        assert_eq!(to_official_utf8(&input), "czacha"); // TODO jak ogarnąć "CH?"
+        assert_eq!(to_official_utf8(&input), "czacha");
+        let input = vec![Hx, E, R, B, A, T, A];
+        assert_eq!(to_official_utf8(&input), "herbata");
         let input = vec![L, I, T, W, O];
         assert_eq!(to_official_utf8(&input), "litwo");
         let input = vec![Tx, E, P, L, U, T, K, O];
@@ -600,4 +561,19 @@ mod tests {
         );
         assert_eq!(result.parts[5], TextRepr::Arbitrary("!".to_string()));
     }
+
+    #[test]
+    fn decomposed() {
+        assert_eq!(
+            parse("Z\u{307}o\u{301}\u{142}te\u{328} z\u{307}o\u{301}\u{142}te\u{328}"),
+            parse("Żółtę żółtę")
+        );
+        assert_eq!(OfficialScript.encode(&parse("ge\u{328}s\u{301}")), "gęś");
+        assert_eq!(
+            try_parse("e\u{328}e\u{328} ж"),
+            Err(Error::UnknownLetter {
+                offset: 5, ch: 'ж'
+            })
+        );
+    }
 }
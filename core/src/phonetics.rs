@@ -0,0 +1,217 @@
+//! How words are pronounced rather than how they are spelled.
+//!
+//! [`Sound`]s coming out of a parser follow the spelling: `chleb` is
+//! `[H, L, E, B]` although it is said [xlɛp]. [`realize`] rewrites such a word
+//! into what is actually said, so that any script can be written "as pronounced".
+
+use crate::Sound;
+use crate::official::{Text, TextRepr};
+
+/// Rewrites a word from how it is spelled to how it is pronounced:
+/// foreign letters are read the Polish way, nasal vowels are split before stops,
+/// voicing is assimilated inside consonant clusters and the last consonants
+/// of the word lose their voicing.
+pub fn realize(word: &[Sound]) -> Vec<Sound> {
+    let mut result = decompose_nasals(&read_foreign(word));
+    assimilate_voicing(&mut result);
+    result
+}
+
+/// [`realize`]s every word of the text.
+///
+/// Words that follow each other directly, as split by [`crate::convert_lossless`],
+/// are pronounced as one.
+pub fn pronounce(text: &Text) -> Text {
+    let mut parts: Vec<TextRepr> = vec![];
+    for part in &text.parts {
+        match (parts.last_mut(), part) {
+            (Some(TextRepr::Word(word, _)), TextRepr::Word(sounds, _)) => word.extend(sounds),
+            (_, part) => parts.push(part.clone()),
+        }
+    }
+    for part in &mut parts {
+        if let TextRepr::Word(sounds, _) = part {
+            *sounds = realize(sounds);
+        }
+    }
+    Text { parts }
+}
+
+/// v -> w, x -> ks, qu -> kw, th -> t, ph -> f
+fn read_foreign(word: &[Sound]) -> Vec<Sound> {
+    use Sound::*;
+    let mut result = Vec::with_capacity(word.len() + 1);
+    let mut i = 0;
+    while i < word.len() {
+        let (sounds, consumed): (&[Sound], usize) = match (word[i], word.get(i + 1)) {
+            (Q, Some(U)) => (&[K, W], 2),
+            (T, Some(Hx)) => (&[T], 2),
+            (P, Some(Hx)) => (&[F], 2),
+            (Q, _) => (&[K], 1),
+            (V, _) => (&[W], 1),
+            (X, _) => (&[K, S], 1),
+            (s, _) => {
+                result.push(s);
+                i += 1;
+                continue;
+            }
+        };
+        result.extend_from_slice(sounds);
+        i += consumed;
+    }
+    result
+}
+
+/// ą before b/p -> om, ę before t/d -> en, ę at the end -> e, ...
+fn decompose_nasals(word: &[Sound]) -> Vec<Sound> {
+    use Sound::*;
+    let mut result = Vec::with_capacity(word.len() + 2);
+    for (i, &s) in word.iter().enumerate() {
+        let oral = match s {
+            Ex => E,
+            Ox => O,
+            _ => {
+                result.push(s);
+                continue;
+            }
+        };
+        match word.get(i + 1) {
+            Some(B | P) => result.extend([oral, M]),
+            Some(T | D | C | Dz | Ch | Dh | K | G) => result.extend([oral, N]),
+            Some(Tx | Dx) => result.extend([oral, Nx]),
+            Some(L | Lx) => result.push(oral),
+            None if s == Ex => result.push(oral),
+            _ => result.push(s),
+        }
+    }
+    result
+}
+
+fn assimilate_voicing(word: &mut [Sound]) {
+    use Sound::*;
+
+    // regressive, right to left; the end of the word devoices like a voiceless consonant
+    let mut following_voiced = Some(false);
+    for s in word.iter_mut().rev() {
+        match voicing(*s) {
+            Some(_) => {
+                if let Some(voiced) = following_voiced {
+                    *s = with_voicing(*s, voiced);
+                }
+                // "w" and "rz" take the voicing of what follows, but do not pass it on
+                following_voiced = match *s {
+                    W | Rx => None,
+                    _ => voicing(*s),
+                };
+            }
+            None => following_voiced = None,
+        }
+    }
+
+    // progressive, "w" and "rz" after a voiceless consonant: kwas, przy, trzy
+    for i in 1..word.len() {
+        if matches!(word[i], W | Rx) && voicing(word[i - 1]) == Some(false) {
+            word[i] = with_voicing(word[i], false);
+        }
+    }
+}
+
+/// Whether an obstruent is voiced; `None` for vowels and sonorants, which take no part in assimilation.
+fn voicing(s: Sound) -> Option<bool> {
+    use Sound::*;
+    match s {
+        B | D | G | W | Z | Zx | Zh | Rx | Dz | Dx | Dh => Some(true),
+        P | T | K | F | S | Sx | Sh | C | Tx | Ch | H | Hx => Some(false),
+        _ => None,
+    }
+}
+
+fn with_voicing(s: Sound, voiced: bool) -> Sound {
+    use Sound::*;
+    if voiced {
+        match s {
+            P => B,
+            T => D,
+            K => G,
+            F => W,
+            S => Z,
+            Sx => Zx,
+            Sh => Zh,
+            C => Dz,
+            Tx => Dx,
+            Ch => Dh,
+            other => other,
+        }
+    } else {
+        match s {
+            B => P,
+            D => T,
+            G => K,
+            W => F,
+            Z => S,
+            Zx => Sx,
+            Zh | Rx => Sh,
+            Dz => C,
+            Dx => Tx,
+            Dh => Ch,
+            other => other,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::greek::GreekScript;
+    use crate::official::{OfficialScript, parse};
+    use crate::{Script, convert_pronounced};
+
+    fn pronounced(word: &str) -> String {
+        OfficialScript.encode(&pronounce(&parse(word)))
+    }
+
+    #[test]
+    fn devoicing() {
+        assert_eq!(pronounced("chleb"), "chlep");
+        assert_eq!(pronounced("Bóg"), "Bók");
+        assert_eq!(pronounced("krzew"), "kszef");
+        assert_eq!(pronounced("mosiądz"), "mosionc");
+    }
+
+    #[test]
+    fn assimilation() {
+        assert_eq!(pronounced("wtorek"), "ftorek");
+        assert_eq!(pronounced("prośba"), "proźba");
+        assert_eq!(pronounced("także"), "tagże");
+        assert_eq!(pronounced("liczba"), "lidżba");
+        assert_eq!(pronounced("wszystko"), "fszystko");
+        assert_eq!(pronounced("kwas przy trzcinie"), "kfas pszy tszcinie");
+        assert_eq!(pronounced("dwa swoje"), "dwa sfoje");
+    }
+
+    #[test]
+    fn nasals() {
+        assert_eq!(pronounced("ząb"), "zomp");
+        assert_eq!(pronounced("ręka"), "renka");
+        assert_eq!(pronounced("dzięki"), "dzienki");
+        assert_eq!(pronounced("mięć"), "mieńć");
+        assert_eq!(pronounced("wziął się"), "wzioł sie");
+        assert_eq!(pronounced("kęs wąsa"), "kęs wąsa");
+    }
+
+    #[test]
+    fn foreign() {
+        assert_eq!(pronounced("Volvo"), "Wolwo");
+        assert_eq!(pronounced("taxi"), "taksi");
+        assert_eq!(pronounced("aquapark"), "akfapark");
+        assert_eq!(pronounced("Thomas i Phil"), "Tomas i Fil");
+    }
+
+    #[test]
+    fn any_script() {
+        assert_eq!(
+            convert_pronounced("chleb i wtorek", &OfficialScript, &GreekScript),
+            "χλέπ ί φτορεκ"
+        );
+    }
+}
@@ -0,0 +1,351 @@
+//! Writing rules of a script as data.
+//!
+//! A script is written by a list of rules, one per line, each telling which letters
+//! a sound (or a few sounds) is written with, and where:
+//!
+//! ```text
+//! # ći -> ci, śe -> się
+//! vowel = A E Ex O Ox U Ou
+//! Tx -> c / _ I
+//! Tx -> ci / _ vowel
+//! Tx -> ć
+//! ```
+//!
+//! A rule is `target -> letters / left _ right`. The target, the left context and the
+//! right context are sounds, or classes of sounds defined as `name = sounds or classes`.
+//! The context is optional, and `-` for letters writes nothing. `#` starts a comment.
+//!
+//! A word is written from left to right by the first rule that matches at each
+//! position, looking at the sounds of the word, not at the letters written so far.
+
+use std::fmt;
+
+use crate::{Error, Sound};
+
+/// A set of sounds, one bit per [`Sound`].
+#[derive(Copy, Clone, PartialEq, Eq, Debug, Default)]
+struct Sounds(u64);
+
+impl Sounds {
+    fn single(sound: Sound) -> Self {
+        Sounds(1 << sound as u64)
+    }
+
+    fn contains(self, sound: Sound) -> bool {
+        self.0 & Sounds::single(sound).0 != 0
+    }
+
+    fn sounds(self) -> impl Iterator<Item = Sound> {
+        Sound::ALL
+            .into_iter()
+            .filter(move |&sound| self.contains(sound))
+    }
+}
+
+#[derive(Debug, Clone)]
+struct Rule {
+    left: Vec<Sounds>,
+    target: Vec<Sounds>,
+    right: Vec<Sounds>,
+    letters: String,
+}
+
+impl Rule {
+    fn matches(&self, word: &[Sound], at: usize) -> bool {
+        let fits = |pattern: &[Sounds], start: usize| {
+            word.get(start..start + pattern.len())
+                .is_some_and(|sounds| pattern.iter().zip(sounds).all(|(set, &s)| set.contains(s)))
+        };
+        let end = at + self.target.len();
+        at >= self.left.len()
+            && fits(&self.left, at - self.left.len())
+            && fits(&self.target, at)
+            && fits(&self.right, end)
+    }
+}
+
+/// The writing rules of a script, see the [module documentation](self).
+#[derive(Debug, Clone, Default)]
+pub struct Rules {
+    rules: Vec<Rule>,
+    /// For every sound, the rules whose target starts with it, in order.
+    by_sound: Vec<Vec<usize>>,
+}
+
+/// What one rule wrote.
+pub(crate) struct Step<'a> {
+    pub letters: &'a str,
+    /// How many sounds were written.
+    pub consumed: usize,
+    /// Whether the rule looked at the sounds after the ones it wrote, so that its
+    /// letters are read together with the letters of those: `σ` in `σνά`.
+    pub lookahead: bool,
+}
+
+impl Rules {
+    /// Reads rules written as described in the [module documentation](self).
+    pub fn parse(source: &str) -> Result<Rules, ParseRulesError> {
+        let mut classes: Vec<(String, Sounds)> = vec![];
+        let mut rules = vec![];
+        for (number, line) in source.lines().enumerate() {
+            let error = |message: String| ParseRulesError {
+                line: number + 1,
+                message,
+            };
+            let line = line.split('#').next().unwrap_or_default().trim();
+            if line.is_empty() {
+                continue;
+            }
+
+            let elements = |text: &str| {
+                text.split_whitespace()
+                    .map(|name| element(name, &classes).map_err(error))
+                    .collect::<Result<Vec<Sounds>, ParseRulesError>>()
+            };
+
+            if let Some((name, sounds)) = line.split_once('=') {
+                let name = name.trim();
+                if name.parse::<Sound>().is_ok() || name.contains(char::is_whitespace) {
+                    return Err(error(format!("{name:?} cannot name a class")));
+                }
+                let set = elements(sounds)?
+                    .into_iter()
+                    .fold(Sounds::default(), |all, set| Sounds(all.0 | set.0));
+                classes.retain(|(other, _)| other != name);
+                classes.push((name.to_string(), set));
+                continue;
+            }
+
+            let Some((target, rest)) = line.split_once("->") else {
+                return Err(error("expected `target -> letters`".to_string()));
+            };
+            let (letters, context) = rest.split_once('/').unwrap_or((rest, "_"));
+            let letters = match letters.trim() {
+                "-" => "",
+                letters if letters.is_empty() || letters.contains(char::is_whitespace) => {
+                    return Err(error(format!(
+                        "expected one group of letters, not {letters:?}"
+                    )));
+                }
+                letters => letters,
+            };
+            let context = context.split_whitespace().collect::<Vec<&str>>();
+            let Some(place) = context.iter().position(|&name| name == "_") else {
+                return Err(error("expected `_` in the context".to_string()));
+            };
+            let target = elements(target)?;
+            if target.is_empty() {
+                return Err(error("a rule must write at least one sound".to_string()));
+            }
+            rules.push(Rule {
+                left: elements(&context[..place].join(" "))?,
+                target,
+                right: elements(&context[place + 1..].join(" "))?,
+                letters: letters.to_string(),
+            });
+        }
+        let by_sound = Sound::ALL
+            .iter()
+            .map(|&sound| {
+                let starts = |rule: &Rule| rule.target[0].contains(sound);
+                (0..rules.len()).filter(|&k| starts(&rules[k])).collect()
+            })
+            .collect();
+        Ok(Rules { rules, by_sound })
+    }
+
+    /// Writes a word, failing on the first sound no rule writes.
+    pub fn write(&self, word: &[Sound]) -> Result<String, Error> {
+        match self.write_recovering(word) {
+            (letters, None) => Ok(letters),
+            (_, Some(error)) => Err(error),
+        }
+    }
+
+    /// Writes a word, leaving out the sounds no rule writes.
+    pub(crate) fn write_recovering(&self, word: &[Sound]) -> (String, Option<Error>) {
+        let mut result = String::with_capacity(word.len());
+        let mut error = None;
+        let mut at = 0;
+        while at < word.len() {
+            match self.step(word, at) {
+                Some(step) => {
+                    result.push_str(step.letters);
+                    at += step.consumed;
+                }
+                None => {
+                    error.get_or_insert(Error::UnexpectedSound {
+                        offset: at,
+                        sound: word[at],
+                    });
+                    at += 1;
+                }
+            }
+        }
+        (result, error)
+    }
+
+    /// The rules as JSON, with the letters of every rule given by `spell` and every class
+    /// spelled out as the sounds in it.
+    #[cfg(feature = "json")]
+    pub(crate) fn to_json(&self, spell: impl Fn(&str) -> String) -> serde_json::Value {
+        let sounds = |pattern: &[Sounds]| {
+            pattern
+                .iter()
+                .map(|&set| set.sounds().map(|sound| sound.to_string()).collect())
+                .collect::<Vec<Vec<String>>>()
+        };
+        self.rules
+            .iter()
+            .map(|rule| {
+                serde_json::json!({
+                    "left": sounds(&rule.left),
+                    "sounds": sounds(&rule.target),
+                    "right": sounds(&rule.right),
+                    "letters": spell(&rule.letters),
+                })
+            })
+            .collect()
+    }
+
+    /// Applies the first rule that matches at `at`.
+    pub(crate) fn step(&self, word: &[Sound], at: usize) -> Option<Step<'_>> {
+        let rule = self
+            .by_sound
+            .get(word[at] as usize)?
+            .iter()
+            .map(|&k| &self.rules[k])
+            .find(|rule| rule.matches(word, at))?;
+        Some(Step {
+            letters: &rule.letters,
+            consumed: rule.target.len(),
+            lookahead: !rule.right.is_empty(),
+        })
+    }
+}
+
+/// The rules in the form they are read from, one per line, with every class spelled out as
+/// its sounds joined with `|`.
+impl fmt::Display for Rules {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let sounds = |pattern: &[Sounds]| {
+            pattern
+                .iter()
+                .map(|&set| {
+                    set.sounds()
+                        .map(|sound| sound.to_string())
+                        .collect::<Vec<_>>()
+                })
+                .map(|names| names.join("|"))
+                .collect::<Vec<String>>()
+        };
+        for rule in &self.rules {
+            let letters = if rule.letters.is_empty() {
+                "-"
+            } else {
+                &rule.letters
+            };
+            write!(f, "{} -> {letters}", sounds(&rule.target).join(" "))?;
+            if !rule.left.is_empty() || !rule.right.is_empty() {
+                let context = [
+                    sounds(&rule.left),
+                    vec!["_".to_string()],
+                    sounds(&rule.right),
+                ];
+                write!(f, " / {}", context.concat().join(" "))?;
+            }
+            writeln!(f)?;
+        }
+        Ok(())
+    }
+}
+
+/// A sound or the sounds of a class.
+fn element(name: &str, classes: &[(String, Sounds)]) -> Result<Sounds, String> {
+    if let Ok(sound) = name.parse::<Sound>() {
+        return Ok(Sounds::single(sound));
+    }
+    classes
+        .iter()
+        .find(|(class, _)| class == name)
+        .map(|&(_, set)| set)
+        .ok_or_else(|| format!("{name:?} is neither a sound nor a class"))
+}
+
+/// Returned when rules cannot be read, see [`Rules::parse`].
+#[derive(PartialEq, Eq, Debug, Clone)]
+pub struct ParseRulesError {
+    /// The line of the rule, counted from 1.
+    pub line: usize,
+    pub message: String,
+}
+
+impl fmt::Display for ParseRulesError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "line {}: {}", self.line, self.message)
+    }
+}
+
+impl std::error::Error for ParseRulesError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Sound::*;
+
+    #[test]
+    fn contexts() {
+        let rules = Rules::parse(
+            "
+            vowel = A E  # not all of them
+            Tx -> c / _ I
+            Tx -> ci / _ vowel
+            Tx -> ć
+            A -> a
+            E -> e
+            I -> i
+            J -> - / Tx _
+            ",
+        )
+        .unwrap();
+        assert_eq!(rules.write(&[Tx, I]).unwrap(), "ci");
+        assert_eq!(rules.write(&[Tx, E]).unwrap(), "cie");
+        assert_eq!(rules.write(&[A, Tx]).unwrap(), "ać");
+        assert_eq!(rules.write(&[Tx, J, A]).unwrap(), "ća");
+        assert_eq!(
+            rules.write(&[A, Sx]),
+            Err(Error::UnexpectedSound {
+                offset: 1,
+                sound: Sx
+            })
+        );
+    }
+
+    #[test]
+    fn every_sound_written() {
+        let greek = include_str!("../rules/greek-v1/greek.rules");
+        let scripts = [
+            include_str!("../rules/official.rules"),
+            greek,
+            &[include_str!("../rules/greek-v1/caron.rules"), greek].concat(),
+            &[include_str!("../rules/greek-v1/beta.rules"), greek].concat(),
+            &[include_str!("../rules/greek-v1/soft-labials.rules"), greek].concat(),
+        ];
+        for source in scripts {
+            let rules = Rules::parse(source).unwrap();
+            for sound in Sound::ALL {
+                assert!(rules.write(&[sound]).is_ok(), "{sound}");
+            }
+        }
+    }
+
+    #[test]
+    fn errors() {
+        let line = |source: &str| Rules::parse(source).unwrap_err().line;
+        assert_eq!(line("A -> a\nTx => ć"), 2);
+        assert_eq!(line("A -> a / b _"), 1);
+        assert_eq!(line("A -> a b"), 1);
+        assert_eq!(line("\n\nSx = A"), 3);
+        assert_eq!(line("A -> a / A"), 1);
+    }
+}
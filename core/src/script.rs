@@ -0,0 +1,632 @@
+use std::borrow::Cow;
+use std::ops::Range;
+use std::sync::Arc;
+
+use crate::align::read_spans;
+use crate::official::{Text, TextRepr};
+use crate::phonetics::pronounce;
+use crate::{CapitalisationMode, Converter, ConverterBuilder, Error, Sound, fold_case};
+
+/// A way of writing Polish.
+///
+/// A script reads text into the [`Sound`] layer and writes it back out,
+/// so any two scripts can be converted into each other with [`convert`].
+pub trait Script: Send + Sync {
+    /// The name the script is selected by, e.g. on the command line.
+    fn name(&self) -> &str;
+
+    /// Other names the script can be selected by.
+    fn aliases(&self) -> &[&str] {
+        &[]
+    }
+
+    /// A short human readable description.
+    fn description(&self) -> &str;
+
+    /// Reads text written in this script.
+    ///
+    /// Never fails: letters that cannot be read are kept as [`TextRepr::Arbitrary`].
+    fn decode(&self, input: &str) -> Text;
+
+    /// Like [`Script::decode`], but fails on the first letter that cannot be read.
+    fn try_decode(&self, input: &str) -> Result<Text, Error>;
+
+    /// Like [`Script::decode`], but a word whose spelling would not be written back the same
+    /// is split into adjacent words that are, see [`convert_lossless`].
+    fn decode_lossless(&self, input: &str) -> Text {
+        self.decode(input)
+    }
+
+    /// Writes a single word in lowercase.
+    ///
+    /// Never fails: sounds that do not fit their context are written on their own.
+    fn encode_word(&self, sounds: &[Sound]) -> String;
+
+    /// Like [`Script::encode_word`], but appends the word to `output`, so that a whole text
+    /// is written into one buffer.
+    fn encode_word_into(&self, sounds: &[Sound], output: &mut String) {
+        output.push_str(&self.encode_word(sounds));
+    }
+
+    /// Like [`Script::encode_word`], but fails on the first sound that does not fit its context.
+    fn try_encode_word(&self, sounds: &[Sound]) -> Result<String, Error> {
+        Ok(self.encode_word(sounds))
+    }
+
+    /// Whether the script has upper and lower case letters.
+    fn has_case(&self) -> bool {
+        true
+    }
+
+    /// Writes text between words (punctuation, spaces, ...) the way this script does,
+    /// e.g. `?` as `;` in Greek.
+    fn localize_punctuation<'a>(&self, arbitrary: &'a str) -> Cow<'a, str> {
+        Cow::Borrowed(arbitrary)
+    }
+
+    /// Written between two words that follow each other directly, so that they are
+    /// read back as two words. Empty if the script has no such mark.
+    fn break_marker(&self) -> &str {
+        ""
+    }
+
+    /// Whether a word is written starting with a mark that already keeps it apart from the
+    /// word before it, so that no [`Script::break_marker`] is written before it.
+    fn starts_with_break(&self, word: &str) -> bool {
+        word.starts_with(self.break_marker())
+    }
+
+    /// Marks every char of text between words that this script would read as a letter
+    /// or as a mark on one, so that it is read back as it is, see [`convert_lossless`].
+    fn escape<'a>(&self, arbitrary: &'a str) -> Cow<'a, str> {
+        Cow::Borrowed(arbitrary)
+    }
+
+    /// Writes a whole text, capitalising the words the way they were in the source.
+    fn encode(&self, text: &Text) -> String {
+        encode_with(self, text, true, None, |sounds, output| {
+            self.encode_word_into(sounds, output);
+            Ok(())
+        })
+        .expect("encode_word never fails")
+    }
+
+    /// Like [`Script::encode`], but fails on the first sound that does not fit its context.
+    fn try_encode(&self, text: &Text) -> Result<String, Error> {
+        encode_with(self, text, true, None, |sounds, output| {
+            output.push_str(&self.try_encode_word(sounds)?);
+            Ok(())
+        })
+    }
+}
+
+/// Writes a whole text the way [`Script::encode`] does, with every word appended to the
+/// result by `write` and punctuation localized only if asked to.
+///
+/// If given `ranges`, tells where every part of the text was written in the result, in bytes.
+pub(crate) fn encode_with<S: Script + ?Sized>(
+    script: &S,
+    text: &Text,
+    localize: bool,
+    mut ranges: Option<&mut Vec<Range<usize>>>,
+    mut write: impl FnMut(&[Sound], &mut String) -> Result<(), Error>,
+) -> Result<String, Error> {
+    let mut result = String::with_capacity(text.parts.len() * 8);
+    let mut after_word = false;
+    for part in &text.parts {
+        let mut start = result.len();
+        match part {
+            TextRepr::Word(sounds, CapitalisationMode::Mixed(mask)) if script.has_case() => {
+                let mut word = String::new();
+                write(sounds, &mut word)?;
+                result.push_str(&mixed_case(script, sounds, &word, mask));
+            }
+            TextRepr::Word(sounds, capitalisation_mode) => {
+                write(sounds, &mut result)?;
+                if script.has_case() {
+                    capitalisation_mode.apply_from(&mut result, start);
+                }
+            }
+            TextRepr::Arbitrary(text) if localize => {
+                result.push_str(&script.localize_punctuation(text))
+            }
+            TextRepr::Arbitrary(text) => result.push_str(text),
+        }
+        let word = matches!(part, TextRepr::Word(..));
+        // a word written starting with a break needs no other one
+        let marker = script.break_marker();
+        if after_word && word && !script.starts_with_break(&result[start..]) {
+            result.insert_str(start, marker);
+            start += marker.len();
+        }
+        after_word = word;
+        if let Some(ranges) = ranges.as_deref_mut() {
+            ranges.push(start..result.len());
+        }
+    }
+    Ok(result)
+}
+
+/// Where the letters of every sound start in a word written with them, in `char`s.
+///
+/// The letters of a sound start where writing the sounds before it stops agreeing
+/// with the whole word, so this works for any script without knowing its rules.
+pub(crate) fn sound_starts<S: Script + ?Sized>(
+    script: &S,
+    sounds: &[Sound],
+    letters: &[char],
+) -> Vec<usize> {
+    let mut starts = Vec::with_capacity(sounds.len());
+    for k in 0..sounds.len() {
+        let before = script.encode_word(&sounds[..k]);
+        let agreeing = before
+            .chars()
+            .zip(letters)
+            .take_while(|&(a, &b)| a == fold_case(b))
+            .count();
+        starts.push(agreeing.max(starts.last().copied().unwrap_or(0)));
+    }
+    starts
+}
+
+/// Capitalises the letters of a written word that were written for the uppercase sounds.
+fn mixed_case<S: Script + ?Sized>(
+    script: &S,
+    sounds: &[Sound],
+    word: &str,
+    mask: &[bool],
+) -> String {
+    let letters = word.chars().collect::<Vec<char>>();
+    let starts = sound_starts(script, sounds, &letters);
+
+    let mut result = String::new();
+    for (j, &c) in letters.iter().enumerate() {
+        // the sound the letter belongs to, or any uppercase sound starting at it
+        let owner = starts.iter().rposition(|&start| start <= j);
+        let uppercase = owner.is_some_and(|k| mask.get(k) == Some(&true))
+            || (0..starts.len()).any(|k| starts[k] == j && mask.get(k) == Some(&true));
+        if uppercase {
+            result.extend(c.to_uppercase());
+        } else {
+            result.push(c);
+        }
+    }
+    result
+}
+
+/// Converts text from one script into another, keeping whatever cannot be converted in place.
+pub fn convert(input: &str, from: &dyn Script, to: &dyn Script) -> String {
+    to.encode(&from.decode(input))
+}
+
+/// Like [`convert`], but fails on the first letter or sound that cannot be converted.
+pub fn try_convert(input: &str, from: &dyn Script, to: &dyn Script) -> Result<String, Error> {
+    to.try_encode(&from.try_decode(input)?)
+}
+
+/// Like [`convert`], but makes sure the text comes back the same when converted back.
+///
+/// Words whose spelling the target script cannot tell apart from another one
+/// (`kja` and `kia` are both `[K, J, A]`), and words the target script would read back
+/// as other sounds (`[L, Y]` is written `λί` in Greek, read as `[L, I]`), are split and
+/// the pieces are separated with [`Script::break_marker`]. A word in capitals the target
+/// script would read as only starting with one (`JA` is `Ά` in Greek, read as `Ja`) gets
+/// the marker after it: `Ά\`. Whatever the target script would read as a letter in the text
+/// between words is marked with [`Script::escape`].
+///
+/// A target script without a break marker, such as IPA or X-SAMPA, has no way to mark a word,
+/// so for it this is the same as [`convert`] apart from the escaped text between words.
+pub fn convert_lossless(input: &str, from: &dyn Script, to: &dyn Script) -> String {
+    to.encode(&split_unreadable(from.decode_lossless(input), from, to))
+}
+
+/// Splits every word `to` would not read back as the same sounds into adjacent words that
+/// it does, as long as `from` still spells the pieces together the way it spelled the word,
+/// marks words in capitals `to` would not read back as such, and escapes the text between
+/// words for `to`.
+pub(crate) fn split_unreadable(text: Text, from: &dyn Script, to: &dyn Script) -> Text {
+    let reads_back = |sounds: &[Sound]| match &to.decode(&to.encode_word(sounds)).parts[..] {
+        [TextRepr::Word(read, _)] => read == sounds,
+        _ => false,
+    };
+    let mut parts = Vec::with_capacity(text.parts.len());
+    // where the words written together before the current part start
+    let mut words = 0;
+    for part in text.parts {
+        let (sounds, case) = match part {
+            TextRepr::Word(sounds, case) => (sounds, case),
+            TextRepr::Arbitrary(arbitrary) => {
+                let arbitrary = TextRepr::Arbitrary(to.escape(&arbitrary).into_owned());
+                mark_capitals(&mut parts, words, Some(&arbitrary), to);
+                parts.push(arbitrary);
+                words = parts.len();
+                continue;
+            }
+        };
+        if to.break_marker().is_empty() || reads_back(&sounds) {
+            parts.push(TextRepr::Word(sounds, case));
+            continue;
+        }
+        let spelling = from.encode_word(&sounds);
+        let (mut start, mut spelled) = (0, 0);
+        while start < sounds.len() {
+            // the longest piece that is read back and spelled the same, or the rest
+            let end = (start + 1..=sounds.len())
+                .rev()
+                .find(|&end| {
+                    let piece = &sounds[start..end];
+                    let rest = &sounds[end..];
+                    let (piece_spelling, rest_spelling) =
+                        (from.encode_word(piece), from.encode_word(rest));
+                    reads_back(piece)
+                        && spelling[spelled..].starts_with(&piece_spelling)
+                        && spelling[spelled + piece_spelling.len()..] == rest_spelling
+                })
+                .unwrap_or(sounds.len());
+            parts.push(TextRepr::Word(
+                sounds[start..end].to_vec(),
+                case.part(start..end),
+            ));
+            spelled += from.encode_word(&sounds[start..end]).len();
+            start = end;
+        }
+    }
+    mark_capitals(&mut parts, words, None, to);
+    Text { parts }
+}
+
+/// Puts an empty word after the words written together from `words` on, if they are
+/// in capitals and `to` would read them as only starting with one (`Ά` for `JA`), so that
+/// `to` writes its break marker after them: `Ά\`. Only if that marker is read as such
+/// and leaves the text after the words, `next`, as it was.
+fn mark_capitals(
+    parts: &mut Vec<TextRepr>,
+    words: usize,
+    next: Option<&TextRepr>,
+    to: &dyn Script,
+) {
+    let count = parts.len() - words;
+    let in_capitals = |read: &[TextRepr]| {
+        read.len() >= count
+            && read[..count]
+                .iter()
+                .all(|word| matches!(word, TextRepr::Word(_, CapitalisationMode::Uppercase)))
+    };
+    if count == 0 || to.break_marker().is_empty() || !to.has_case() || !in_capitals(&parts[words..])
+    {
+        return;
+    }
+    let mark = || TextRepr::Word(vec![], CapitalisationMode::Uppercase);
+    let read = |marked: bool| {
+        let mut written = parts[words..].to_vec();
+        written.extend(marked.then(mark));
+        written.extend(next.cloned());
+        to.decode(&to.encode(&Text { parts: written })).parts
+    };
+    let (unmarked, marked) = (read(false), read(true));
+    if !in_capitals(&unmarked) && in_capitals(&marked) && marked[count..] == unmarked[count..] {
+        parts.push(mark());
+    }
+}
+
+/// A word (or the text around it) that comes back different after converting it to another
+/// script and back.
+#[derive(PartialEq, Eq, Debug, Clone)]
+pub struct RoundtripFailure {
+    /// Where the word starts in the input, in `char`s.
+    pub offset: usize,
+    /// The word as it was written.
+    pub word: String,
+    /// The word after converting it there and back.
+    pub result: String,
+}
+
+/// Converts the input from `from` to `via` and back with [`convert`], and reports what
+/// does not come back the same.
+///
+/// Every line is converted as a whole, words along with the text between them, as lines
+/// are read independently of each other. In a line that comes back different, every run
+/// of text without spaces that does so on its own is reported, only its words if they
+/// come back different without the text around them (`wjazd` of `wjazd.`, but `don't`).
+/// A line in which none does is reported whole.
+pub fn verify_roundtrip(input: &str, from: &dyn Script, via: &dyn Script) -> Vec<RoundtripFailure> {
+    let roundtrip = |text: &str| convert(&convert(text, from, via), via, from);
+    let words = words(input, &from.decode(input), from);
+    let mut failures = vec![];
+    let mut report = |range: Range<usize>, result: String| {
+        failures.push(RoundtripFailure {
+            offset: input[..range.start].chars().count(),
+            word: input[range].to_string(),
+            result,
+        })
+    };
+
+    let mut line_start = 0;
+    for line in input.split_inclusive('\n') {
+        let line_range = line_start..line_start + line.trim_end_matches('\n').len();
+        line_start += line.len();
+        let line = &input[line_range.clone()];
+        let result = roundtrip(line);
+        if result == line {
+            continue;
+        }
+        let mut reported = false;
+        for token in tokens(line) {
+            let token = line_range.start + token.start..line_range.start + token.end;
+            let token_result = roundtrip(&input[token.clone()]);
+            if token_result == input[token.clone()] {
+                continue;
+            }
+            let mut inner = words
+                .iter()
+                .filter(|word| token.start <= word.start && word.end <= token.end);
+            let inner = inner
+                .next()
+                .map(|first| first.start..inner.next_back().unwrap_or(first).end);
+            match inner.map(|inner| (roundtrip(&input[inner.clone()]), inner)) {
+                Some((inner_result, inner)) if inner_result != input[inner.clone()] => {
+                    report(inner, inner_result)
+                }
+                _ => report(token, token_result),
+            }
+            reported = true;
+        }
+        if !reported {
+            report(line_range, result);
+        }
+    }
+    failures
+}
+
+/// Where every run of text without whitespace is in a line, in bytes.
+fn tokens(line: &str) -> impl Iterator<Item = Range<usize>> + '_ {
+    line.split(char::is_whitespace)
+        .filter(|token| !token.is_empty())
+        .map(move |token| {
+            let start = token.as_ptr() as usize - line.as_ptr() as usize;
+            start..start + token.len()
+        })
+}
+
+/// Where the source text of every word of a decoded text is, in bytes.
+/// Words that follow each other directly are taken together.
+fn words(input: &str, text: &Text, from: &dyn Script) -> Vec<Range<usize>> {
+    let mut words: Vec<Range<usize>> = vec![];
+    let mut after_word = false;
+    for (part, span) in text.parts.iter().zip(read_spans(input, text, from)) {
+        match (part, words.last_mut()) {
+            (TextRepr::Word(..), Some(last)) if after_word => last.end = span.range.end,
+            (TextRepr::Word(..), _) => words.push(span.range),
+            (TextRepr::Arbitrary(_), _) => {}
+        }
+        after_word = matches!(part, TextRepr::Word(..));
+    }
+    words
+}
+
+/// Like [`convert`], but writes the words the way they are pronounced, see [`crate::phonetics`].
+pub fn convert_pronounced(input: &str, from: &dyn Script, to: &dyn Script) -> String {
+    to.encode(&pronounce(&from.decode(input)))
+}
+
+/// Like [`convert_pronounced`], but fails on the first letter or sound that cannot be converted.
+pub fn try_convert_pronounced(
+    input: &str,
+    from: &dyn Script,
+    to: &dyn Script,
+) -> Result<String, Error> {
+    to.try_encode(&pronounce(&from.try_decode(input)?))
+}
+
+/// A collection of scripts looked up by name.
+///
+/// [`Registry::default`] contains every script built into the crate.
+pub struct Registry {
+    scripts: Vec<Arc<dyn Script>>,
+}
+
+impl Registry {
+    /// A registry without any scripts.
+    pub fn empty() -> Self {
+        Registry { scripts: vec![] }
+    }
+
+    /// Adds a script. A script registered later wins over earlier ones with the same name.
+    pub fn register(&mut self, script: impl Script + 'static) {
+        self.scripts.insert(0, Arc::new(script));
+    }
+
+    /// Finds a script by its name or one of its aliases.
+    pub fn get(&self, name: &str) -> Option<&dyn Script> {
+        self.find(name).map(|script| script.as_ref())
+    }
+
+    /// Every registered script, in the order they were registered.
+    pub fn scripts(&self) -> impl Iterator<Item = &dyn Script> {
+        self.scripts.iter().rev().map(|script| script.as_ref())
+    }
+
+    /// Starts building a [`Converter`] between two scripts given by name.
+    pub fn converter(&self, from: &str, to: &str) -> Result<ConverterBuilder, Error> {
+        let shared = |name: &str| {
+            self.find(name)
+                .cloned()
+                .ok_or_else(|| Error::UnknownScript(name.to_string()))
+        };
+        Ok(Converter::builder_shared(shared(from)?, shared(to)?))
+    }
+
+    /// Converts text between two scripts given by name.
+    pub fn convert(&self, input: &str, from: &str, to: &str) -> Result<String, Error> {
+        Ok(convert(input, self.lookup(from)?, self.lookup(to)?))
+    }
+
+    /// Like [`Registry::convert`], but fails on the first letter or sound that cannot be converted.
+    pub fn try_convert(&self, input: &str, from: &str, to: &str) -> Result<String, Error> {
+        try_convert(input, self.lookup(from)?, self.lookup(to)?)
+    }
+
+    fn find(&self, name: &str) -> Option<&Arc<dyn Script>> {
+        self.scripts
+            .iter()
+            .find(|script| script.name() == name || script.aliases().contains(&name))
+    }
+
+    fn lookup(&self, name: &str) -> Result<&dyn Script, Error> {
+        self.get(name)
+            .ok_or_else(|| Error::UnknownScript(name.to_string()))
+    }
+}
+
+impl Default for Registry {
+    fn default() -> Self {
+        let mut registry = Registry::empty();
+        registry.register(crate::official::OfficialScript);
+        registry.register(crate::greek::GreekScript);
+        registry.register(crate::greek::GreekTypographicScript);
+        registry.register(crate::cyrillic::CyrillicScript);
+        registry.register(crate::ipa::IpaScript);
+        registry.register(crate::ipa::XSampaScript);
+        registry
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn lookup() {
+        let registry = Registry::default();
+        assert_eq!(registry.get("grecki").map(|s| s.name()), Some("grecki"));
+        assert_eq!(registry.get("o").map(|s| s.name()), Some("oficjalny"));
+        assert!(registry.get("klingoński").is_none());
+        assert_eq!(
+            registry.scripts().map(|s| s.name()).collect::<Vec<_>>(),
+            [
+                "oficjalny",
+                "grecki",
+                "grecki-typograficzny",
+                "cyrylica",
+                "ipa",
+                "x-sampa"
+            ]
+        );
+    }
+
+    #[test]
+    fn roundtrip() {
+        use crate::greek::GreekScript;
+        use crate::official::OfficialScript;
+
+        let input = "Kja i kia, wjazd. Ania ma kota";
+        assert_eq!(
+            verify_roundtrip(input, &OfficialScript, &GreekScript),
+            [
+                RoundtripFailure {
+                    offset: 0,
+                    word: "Kja".to_string(),
+                    result: "Kia".to_string(),
+                },
+                RoundtripFailure {
+                    offset: 11,
+                    word: "wjazd".to_string(),
+                    result: "wiazd".to_string(),
+                },
+            ]
+        );
+
+        // marks between words are read as part of them
+        let failure = |offset, word: &str, result: &str| RoundtripFailure {
+            offset,
+            word: word.to_string(),
+            result: result.to_string(),
+        };
+        assert_eq!(
+            verify_roundtrip(
+                "Don't stop, rock'n'roll!\nala\\ma ' a '",
+                &OfficialScript,
+                &GreekScript
+            ),
+            [
+                failure(0, "Don't", "Dońt"),
+                failure(12, "rock'n'roll", "rockjńroll"),
+                failure(25, "ala\\ma", "alama"),
+            ]
+        );
+
+        let greek = convert_lossless(input, &OfficialScript, &GreekScript);
+        assert_eq!(greek, "Κ'\\α ί κά, β'\\αζδ. Ανά μα κοτα");
+        assert_eq!(convert(&greek, &GreekScript, &OfficialScript), input);
+        let cyrillic = convert_lossless(input, &OfficialScript, &crate::cyrillic::CyrillicScript);
+        assert_eq!(
+            convert(&cyrillic, &crate::cyrillic::CyrillicScript, &OfficialScript),
+            input
+        );
+        assert_eq!(
+            convert(input, &OfficialScript, &GreekScript),
+            "Κά ί κά, βάζδ. Ανά μα κοτα"
+        );
+
+        // ly and li are both written λί in Greek
+        let input = "Lyk i lik, aglyóy";
+        let greek = convert_lossless(input, &OfficialScript, &GreekScript);
+        assert_eq!(greek, "Λ'\\ικ ί λίκ, αγλ'\\ιουι");
+        assert_eq!(convert(&greek, &GreekScript, &OfficialScript), input);
+        let converter = Converter::builder(OfficialScript, GreekScript)
+            .lossless(true)
+            .build();
+        assert_eq!(converter.convert(input).unwrap(), greek);
+
+        // a word in capitals written with one letter is marked, or it would be read as Ja
+        let input = "JA CH, KJA i Ja";
+        let greek = convert_lossless(input, &OfficialScript, &GreekScript);
+        assert_eq!(greek, "Ά\\ Χ\\, Κ'\\Α ί Ά");
+        assert_eq!(convert(&greek, &GreekScript, &OfficialScript), input);
+        let cyrillic = convert_lossless(input, &OfficialScript, &crate::cyrillic::CyrillicScript);
+        assert_eq!(cyrillic, "Яъ Хъ, КЙъА и Я");
+        assert_eq!(
+            convert(&cyrillic, &crate::cyrillic::CyrillicScript, &OfficialScript),
+            input
+        );
+        assert_eq!(convert(input, &OfficialScript, &GreekScript), "Ά Χ, ΚΆ ί Ά");
+
+        // a long word is split without reading it again for every place a piece could end
+        let input = "lykja".repeat(2000);
+        let greek = convert_lossless(&input, &OfficialScript, &GreekScript);
+        assert_eq!(convert(&greek, &GreekScript, &OfficialScript), input);
+
+        // marks between words are escaped, so they are not read as part of a word
+        for (input, escaped) in [
+            ("don't", "δον\\\\'τ"),
+            ("rock'n'roll", "ροτσκ\\\\'ν\\\\'ρολλ'"),
+            ("ala\\ma", "αλά\\\\\\μα"),
+            ("‘ϊ’ radość", "\\\\‘\\\\ϊ\\\\’ ραδοστ'"),
+        ] {
+            let greek = convert_lossless(input, &OfficialScript, &GreekScript);
+            assert_eq!(greek, escaped);
+            assert_eq!(convert(&greek, &GreekScript, &OfficialScript), input);
+        }
+    }
+
+    #[test]
+    fn any_to_any() {
+        let registry = Registry::default();
+        assert_eq!(
+            registry.convert("Ania ma kota", "o", "g"),
+            Ok("Ανά μα κοτα".to_string())
+        );
+        assert_eq!(
+            registry.convert("Ανά μα κοτα", "grecki", "oficjalny"),
+            Ok("Ania ma kota".to_string())
+        );
+        assert_eq!(
+            registry.convert("Ania", "grecki", "marsjański"),
+            Err(Error::UnknownScript("marsjański".to_string()))
+        );
+        assert_eq!(
+            registry.try_convert("ανά, ala", "g", "o"),
+            Err(Error::UnknownLetter { offset: 5, ch: 'a' })
+        );
+    }
+}
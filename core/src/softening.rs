@@ -0,0 +1,145 @@
+//! Where softening is written, shared by the scripts that mark it on the following letter.
+//!
+//! Greek and Cyrillic both write `śmiać` as a run of plain consonants with the
+//! softening carried by the vowel after them (`σμ` + `ά`, `см` + `я`),
+//! so deciding how a word splits into such runs does not depend on the script.
+
+use crate::Sound;
+
+/// How a part of a word is written, before picking the actual letters.
+#[derive(PartialEq, Eq, Debug, Clone, Copy)]
+pub(crate) enum Piece {
+    /// A sound written the usual way.
+    Plain(Sound),
+    /// A softened consonant written like its hard counterpart, softened by what follows.
+    SoftBase(Sound),
+    /// A vowel that also softens the consonants before it, or stands for `j` + vowel.
+    SoftVowel(Sound),
+    /// Softening of the consonants before it, when no vowel follows.
+    SoftMark,
+    /// `j` on its own.
+    Glide,
+    /// Keeps the consonant before it from being read as softened.
+    Break,
+}
+
+pub(crate) struct ConsumeResult {
+    pub result: Vec<Piece>,
+    pub consumed: usize,
+}
+
+/// Takes the pieces for the beginning of the input.
+pub(crate) fn consume(input: &[Sound]) -> ConsumeResult {
+    if input.is_empty() {
+        return ConsumeResult {
+            result: vec![],
+            consumed: 0,
+        };
+    }
+
+    let i0 = input[0];
+
+    if input.len() == 1 {
+        return ConsumeResult {
+            result: vec![Piece::Plain(i0)],
+            consumed: 1,
+        };
+    }
+
+    let mut softened_count = 0;
+    while softened_count < input.len()
+        && input[softened_count].is_softened()
+        && input[softened_count] != Sound::I
+    {
+        softened_count += 1;
+    }
+
+    if softened_count > 0 {
+        let following_vowel = input.get(softened_count).cloned().filter(|x| x.is_vowel());
+        let total_consumed = softened_count + following_vowel.map_or(0, |_| 1);
+
+        let mut result = input[..softened_count]
+            .iter()
+            .map(|&s| Piece::SoftBase(s))
+            .collect::<Vec<Piece>>();
+
+        result.push(match following_vowel {
+            Some(vowel) => Piece::SoftVowel(vowel),
+            None => Piece::SoftMark,
+        });
+
+        return ConsumeResult {
+            result,
+            consumed: total_consumed,
+        };
+    }
+
+    use Sound::*;
+    if i0 == Sound::J {
+        let i1 = input[1];
+        if i1.is_vowel() && i1 != Y && i1 != I {
+            return ConsumeResult {
+                result: vec![Piece::SoftVowel(i1)],
+                consumed: 2,
+            };
+            // TODO Case for I Y
+        } else {
+            return ConsumeResult {
+                result: vec![Piece::Glide],
+                consumed: 1,
+            };
+        }
+    }
+
+    // check for break
+    let i1 = input[1];
+    if i1 == J || i1 == I {
+        //|| i1 == Nx || i1 == Zx || i1 == Sx || i1 == Tx  || i1 == Dx ||i1==Rx{
+        if i0 == N || i0 == Z || i0 == S || i0 == T || i0 == D || i0 == R {
+            return ConsumeResult {
+                result: vec![Piece::Plain(i0), Piece::Break],
+                consumed: 1,
+            };
+        }
+    }
+
+    ConsumeResult {
+        result: vec![Piece::Plain(i0)],
+        consumed: 1,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Piece::*;
+    use super::*;
+    use crate::Sound::*;
+
+    fn pieces(mut input: &[Sound]) -> Vec<Piece> {
+        let mut result = vec![];
+        while !input.is_empty() {
+            let consume_result = consume(input);
+            result.extend(consume_result.result);
+            input = &input[consume_result.consumed..];
+        }
+        result
+    }
+
+    #[test]
+    fn split() {
+        assert_eq!(
+            pieces(&[Sx, M, J, A, Tx]),
+            [SoftBase(Sx), SoftMark, Plain(M), SoftVowel(A), Plain(Tx)]
+        );
+        assert_eq!(
+            pieces(&[Sx, Tx, I]),
+            [SoftBase(Sx), SoftBase(Tx), SoftVowel(I)]
+        );
+        assert_eq!(pieces(&[Z, J, E]), [Plain(Z), Break, SoftVowel(E)]);
+        assert_eq!(pieces(&[O, J]), [Plain(O), Plain(J)]);
+        assert_eq!(
+            pieces(&[J, O, J, N, A]),
+            [SoftVowel(O), Glide, Plain(N), Plain(A)]
+        );
+    }
+}
@@ -0,0 +1,137 @@
+//! Reading letters into sounds from a table of graphemes.
+//!
+//! A script's reading rules are a list of graphemes (`sz`, `cia`, `τσ`) and the sounds
+//! they stand for. [`Trie`] compiles such a list once and reads a word by always taking
+//! the longest grapheme that matches, so a word is read in time linear in its length
+//! and a new rule is one more entry in the table.
+
+use crate::Sound;
+
+/// Graphemes made of letters of type `T`, with the sounds each of them is read as.
+#[derive(Debug, Clone)]
+pub(crate) struct Trie<T> {
+    nodes: Vec<Node<T>>,
+}
+
+#[derive(Debug, Clone)]
+struct Node<T> {
+    /// Sorted by the letter, so that a letter is found by binary search.
+    children: Vec<(T, usize)>,
+    sounds: Option<Vec<Sound>>,
+}
+
+impl<T> Default for Node<T> {
+    fn default() -> Self {
+        Node {
+            children: vec![],
+            sounds: None,
+        }
+    }
+}
+
+impl<T: Copy + Ord> Trie<T> {
+    pub fn new() -> Self {
+        Trie {
+            nodes: vec![Node::default()],
+        }
+    }
+
+    /// Adds a grapheme, replacing the sounds of the same grapheme if it was already there.
+    pub fn insert(&mut self, grapheme: impl IntoIterator<Item = T>, sounds: &[Sound]) {
+        let mut node = 0;
+        for letter in grapheme {
+            let children = &self.nodes[node].children;
+            node = match children.binary_search_by_key(&letter, |&(l, _)| l) {
+                Ok(at) => children[at].1,
+                Err(at) => {
+                    let child = self.nodes.len();
+                    self.nodes[node].children.insert(at, (letter, child));
+                    self.nodes.push(Node::default());
+                    child
+                }
+            };
+        }
+        self.nodes[node].sounds = Some(sounds.to_vec());
+    }
+
+    /// The longest grapheme at the start of `input`: its sounds and its length.
+    pub fn longest_match(&self, input: &[T]) -> Option<(&[Sound], usize)> {
+        let mut node = 0;
+        let mut found = None;
+        for (len, &letter) in input.iter().enumerate() {
+            let Some(child) = self.child(node, letter) else {
+                break;
+            };
+            node = child;
+            if let Some(sounds) = &self.nodes[node].sounds {
+                found = Some((&sounds[..], len + 1));
+            }
+        }
+        found
+    }
+
+    /// Whether a grapheme starts with `letter`.
+    pub fn starts(&self, letter: T) -> bool {
+        self.child(0, letter).is_some()
+    }
+
+    /// Every letter of every grapheme.
+    #[cfg(feature = "script-files")]
+    pub fn letters(&self) -> impl Iterator<Item = T> + '_ {
+        self.nodes
+            .iter()
+            .flat_map(|node| node.children.iter().map(|&(letter, _)| letter))
+    }
+
+    /// The letters some grapheme goes on after.
+    pub fn inner_letters(&self) -> impl Iterator<Item = T> + '_ {
+        self.nodes.iter().flat_map(|node| {
+            node.children
+                .iter()
+                .filter(|&&(_, child)| !self.nodes[child].children.is_empty())
+                .map(|&(letter, _)| letter)
+        })
+    }
+
+    fn child(&self, node: usize, letter: T) -> Option<usize> {
+        let children = &self.nodes[node].children;
+        children
+            .binary_search_by_key(&letter, |&(l, _)| l)
+            .ok()
+            .map(|at| children[at].1)
+    }
+}
+
+impl<G: IntoIterator<Item = T>, T: Copy + Ord> FromIterator<(G, Vec<Sound>)> for Trie<T> {
+    fn from_iter<I: IntoIterator<Item = (G, Vec<Sound>)>>(entries: I) -> Self {
+        let mut trie = Trie::new();
+        for (grapheme, sounds) in entries {
+            trie.insert(grapheme, &sounds);
+        }
+        trie
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Sound::*;
+
+    #[test]
+    fn longest_match() {
+        let trie = [("c", vec![C]), ("cz", vec![Ch]), ("cia", vec![Tx, A])]
+            .into_iter()
+            .map(|(grapheme, sounds)| (grapheme.chars(), sounds))
+            .collect::<Trie<char>>();
+        let read = |word: &str| {
+            let word = word.chars().collect::<Vec<char>>();
+            trie.longest_match(&word)
+                .map(|(sounds, len)| (sounds.to_vec(), len))
+        };
+        assert_eq!(read("czy"), Some((vec![Ch], 2)));
+        assert_eq!(read("ciasto"), Some((vec![Tx, A], 3)));
+        assert_eq!(read("cisza"), Some((vec![C], 1)));
+        assert_eq!(read("ala"), None);
+        assert!(trie.starts('c') && !trie.starts('z'));
+    }
+}
@@ -1,5 +1,11 @@
+use std::sync::LazyLock;
+
+use beciarz_core::Registry;
 use wasm_bindgen::prelude::*;
 
+/// Every script, built once rather than on every call.
+static REGISTRY: LazyLock<Registry> = LazyLock::new(Registry::default);
+
 #[wasm_bindgen]
 pub fn js_official_to_greek(input: &str) -> JsValue {
     JsValue::from_str(&beciarz_core::official_to_greek(input))
@@ -9,3 +15,20 @@ pub fn js_official_to_greek(input: &str) -> JsValue {
 pub fn js_greek_to_official(input: &str) -> JsValue {
     JsValue::from_str(&beciarz_core::greek_to_official(input))
 }
+
+/// Names of every script that can be passed to `js_convert`.
+#[wasm_bindgen]
+pub fn js_scripts() -> Vec<String> {
+    REGISTRY
+        .scripts()
+        .map(|script| script.name().to_string())
+        .collect()
+}
+
+/// Converts `input` between two scripts given by name (or alias).
+#[wasm_bindgen]
+pub fn js_convert(input: &str, from: &str, to: &str) -> Result<String, JsValue> {
+    REGISTRY
+        .convert(input, from, to)
+        .map_err(|e| JsValue::from_str(&e.to_string()))
+}